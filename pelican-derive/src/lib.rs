@@ -0,0 +1,419 @@
+//! Derive macro for pelican's `Unify` trait, use it through the `derive`
+//! feature of pelican rather than depending on this crate directly
+#![warn(elided_lifetimes_in_paths)]
+#![warn(missing_docs)]
+#![warn(unreachable_pub)]
+#![warn(unused_import_braces)]
+#![warn(unused_lifetimes)]
+#![warn(unused_qualifications)]
+#![deny(unsafe_code)]
+#![deny(unused_results)]
+#![warn(clippy::pedantic)]
+#![allow(clippy::doc_markdown)]
+
+use proc_macro::TokenStream;
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::{ToTokens, format_ident, quote};
+use syn::{
+    Data, DeriveInput, Field, Fields, GenericArgument, Ident, Member, Path,
+    PathArguments, Type, parse_macro_input, parse_quote,
+};
+
+/// Derive `Unify` for a type built out of nested `ValueOrVar`s
+///
+/// The generated implementation unifies structurally:
+/// * A variable is unified with another variable or bound to a value.
+/// * Two values built with the same variant are unified field by field. Fields
+///   of type `Box<ValueOrVar<_>>` are unified recursively with
+///   `Unifier::unify`, any other field must compare equal with `==`.
+/// * Anything else, e.g. two different variants, is passed to the merge
+///   function, which usually reports an error.
+///
+/// `normalize` resolves variables at any depth, and `children` and
+/// `children_mut` list the recursive fields. That is enough for
+/// `TableBuilder::occurs_check`, `Unifier::occurs` and generalization.
+///
+/// The type is configured with a `#[unify(...)]` attribute:
+/// * `error = Type` (required): the `Unify::Error` type.
+/// * `merge = path` (required): a `fn(&Self, &Self) -> Result<Self, Error>`
+///   used as `Unify::merge`. Usually it returns the value if both sides are
+///   equal and an error if they aren't.
+/// * `occurs = path`: a `fn(Var, Self) -> Error`. If it is set, the occurs
+///   check runs before a variable is bound to a value, whatever options the
+///   table was built with.
+/// * `crate = path`: where to find pelican, defaults to `::pelican`.
+///
+/// # Occurs check
+/// The occurs check is opt in. Without `occurs` a variable can be bound to a
+/// value containing itself, producing an infinite value, unless the table was
+/// built with `TableBuilder::occurs_check`.
+///
+/// # Recursive fields
+/// Recursive fields are found by looking at the field's type. The type must
+/// be written as `Box<ValueOrVar<_>>`, with or without a path in front of
+/// either name. The macro can't see through a type alias, mark a field whose
+/// type is an alias for `Box<ValueOrVar<_>>` with `#[unify(recursive)]`. Any
+/// other field which mentions `ValueOrVar` is rejected rather than compared
+/// with `==`.
+///
+/// ```ignore
+/// #[derive(Debug, Clone, PartialEq, Unify)]
+/// #[unify(error = TypeError, merge = Type::equal, occurs = TypeError::Infinite)]
+/// enum Type {
+///     Int,
+///     Function(Box<ValueOrVar<Type>>, Box<ValueOrVar<Type>>),
+/// }
+/// ```
+#[proc_macro_derive(Unify, attributes(unify))]
+pub fn derive_unify(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// Contents of the #[unify(...)] attributes
+struct Attributes {
+    krate: Path,
+    error: Type,
+    merge: Path,
+    occurs: Option<Path>,
+}
+
+impl Attributes {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut krate = None;
+        let mut error = None;
+        let mut merge = None;
+        let mut occurs = None;
+        for attr in &input.attrs {
+            if !attr.path().is_ident("unify") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("crate") {
+                    krate = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("error") {
+                    error = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("merge") {
+                    merge = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("occurs") {
+                    occurs = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("Unknown unify attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        let missing = |name| {
+            syn::Error::new_spanned(
+                &input.ident,
+                format!("Missing #[unify({name} = ...)] attribute"),
+            )
+        };
+        Ok(Self {
+            krate: krate.unwrap_or_else(|| parse_quote!(::pelican)),
+            error: error.ok_or_else(|| missing("error"))?,
+            merge: merge.ok_or_else(|| missing("merge"))?,
+            occurs,
+        })
+    }
+}
+
+// A Box<ValueOrVar<_>>, only the names are checked so the types can be
+// imported under any path
+fn recursive(ty: &Type) -> bool {
+    argument(ty, "Box")
+        .is_some_and(|inner| argument(inner, "ValueOrVar").is_some())
+}
+
+// True if `name` appears anywhere in the tokens
+fn mentions(tokens: TokenStream2, name: &str) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == name,
+        TokenTree::Group(group) => mentions(group.stream(), name),
+        TokenTree::Punct(_) | TokenTree::Literal(_) => false,
+    })
+}
+
+// Whether the field is unified recursively, either from its type or its
+// #[unify(recursive)] attribute
+fn classify(field: &Field) -> syn::Result<bool> {
+    let mut marked = false;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("unify") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("recursive") {
+                marked = true;
+                Ok(())
+            } else {
+                Err(meta.error("Unknown unify field attribute"))
+            }
+        })?;
+    }
+    if marked || recursive(&field.ty) {
+        return Ok(true);
+    }
+    if mentions(field.ty.to_token_stream(), "ValueOrVar") {
+        return Err(syn::Error::new_spanned(
+            &field.ty,
+            "Fields containing a ValueOrVar must be a Box<ValueOrVar<_>>, \
+             mark an alias for one with #[unify(recursive)]",
+        ));
+    }
+    Ok(false)
+}
+
+// The type argument of `ty` if the last segment of its path is `name`
+fn argument<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != name {
+        return None;
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    arguments.args.iter().find_map(|argument| match argument {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
+
+// A struct or one variant of an enum. Each field is paired with whether it is
+// unified recursively
+struct Shape {
+    path: TokenStream2,
+    named: bool,
+    fields: Vec<(Member, bool)>,
+}
+
+impl Shape {
+    fn new(path: TokenStream2, fields: &Fields) -> syn::Result<Self> {
+        Ok(Self {
+            path,
+            named: matches!(fields, Fields::Named(_)),
+            fields: fields
+                .members()
+                .zip(fields.iter())
+                .map(|(member, field)| Ok((member, classify(field)?)))
+                .collect::<syn::Result<_>>()?,
+        })
+    }
+
+    // Build a pattern or expression with one part per field
+    fn build(
+        &self,
+        parts: impl IntoIterator<Item = TokenStream2>,
+    ) -> TokenStream2 {
+        let path = &self.path;
+        let parts = parts.into_iter();
+        if self.fields.is_empty() {
+            quote!(#path)
+        } else if self.named {
+            let members = self.fields.iter().map(|(member, _)| member);
+            quote!(#path { #(#members: #parts),* })
+        } else {
+            quote!(#path(#(#parts),*))
+        }
+    }
+
+    fn names(&self, prefix: &str) -> Vec<Ident> {
+        (0..self.fields.len())
+            .map(|i| format_ident!("{prefix}_{i}"))
+            .collect()
+    }
+
+    fn has_plain(&self) -> bool {
+        self.fields.iter().any(|&(_, recursive)| !recursive)
+    }
+
+    // Plain fields must be equal, recursive ones are unified in order
+    fn unify_arm(&self) -> TokenStream2 {
+        let left = self.names("left");
+        let right = self.names("right");
+        let mut guard = Vec::new();
+        let mut recurse = Vec::new();
+        for (((_, recursive), left), right) in
+            self.fields.iter().zip(&left).zip(&right)
+        {
+            if *recursive {
+                recurse.push(quote!(unifier.unify(*#left, *#right)?;));
+            } else {
+                guard.push(quote!(#left == #right));
+            }
+        }
+        let guard = (!guard.is_empty()).then(|| quote!(if #(#guard)&&*));
+        let left = self.build(left.iter().map(|name| quote!(#name)));
+        let right = self.build(right.iter().map(|name| quote!(#name)));
+        quote! {
+            (#left, #right) #guard => {
+                #(#recurse)*
+                ::core::result::Result::Ok(())
+            }
+        }
+    }
+
+    fn normalize_arm(&self, this: &TokenStream2) -> TokenStream2 {
+        let names = self.names("field");
+        let pattern = self.build(names.iter().map(|name| quote!(#name)));
+        let value = self.build(self.fields.iter().zip(&names).map(
+            |((_, recursive), name)| {
+                if *recursive {
+                    quote! {
+                        ::std::boxed::Box::new(#this::normalize(*#name, unifier))
+                    }
+                } else {
+                    quote!(#name)
+                }
+            },
+        ));
+        quote!(#pattern => #value,)
+    }
+
+    // Bind the recursive fields and ignore the rest
+    fn children_arm(&self, mutable: bool) -> TokenStream2 {
+        let names = self.names("field");
+        let fields = self.fields.iter().zip(&names);
+        let pattern =
+            self.build(fields.clone().map(|((_, recursive), name)| {
+                if *recursive { quote!(#name) } else { quote!(_) }
+            }));
+        let children =
+            fields
+                .filter(|((_, recursive), _)| *recursive)
+                .map(|(_, name)| {
+                    if mutable {
+                        quote!(&mut **#name)
+                    } else {
+                        quote!(&**#name)
+                    }
+                });
+        quote!(#pattern => ::std::vec![#(#children),*],)
+    }
+}
+
+fn shapes(input: &DeriveInput) -> syn::Result<Vec<Shape>> {
+    match &input.data {
+        Data::Struct(data) => Ok(vec![Shape::new(quote!(Self), &data.fields)?]),
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .map(|variant| {
+                let ident = &variant.ident;
+                Shape::new(quote!(Self::#ident), &variant.fields)
+            })
+            .collect(),
+        Data::Union(_) => Err(syn::Error::new_spanned(
+            &input.ident,
+            "Unify can't be derived for unions",
+        )),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Attributes {
+        krate,
+        error,
+        merge,
+        occurs,
+    } = Attributes::parse(input)?;
+    let shapes = shapes(input)?;
+    let unification = quote!(#krate::unification);
+    let value_or_var = quote!(#unification::ValueOrVar);
+    let this = quote!(<Self as #unification::Unify>);
+
+    let arms = shapes.iter().map(Shape::unify_arm);
+    // A lone struct with only recursive fields always matches, the fallback
+    // would be unreachable
+    let fallback = (shapes.len() != 1 || shapes[0].has_plain()).then(|| {
+        quote! {
+            (left, right) => #merge(&left, &right).map(|_| ()),
+        }
+    });
+    let occurs = occurs.map(|occurs| {
+        quote! {
+            if unifier.occurs(var, &value) {
+                return ::core::result::Result::Err(#occurs(var, value));
+            }
+        }
+    });
+    let normalize = shapes.iter().map(|shape| shape.normalize_arm(&this));
+    let children = shapes.iter().map(|shape| shape.children_arm(false));
+    let children_mut = shapes.iter().map(|shape| shape.children_arm(true));
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #unification::Unify for #name #ty_generics
+        #where_clause
+        {
+            type Error = #error;
+
+            fn unify(
+                left: #value_or_var<Self>,
+                right: #value_or_var<Self>,
+                unifier: &mut #unification::Unifier<Self>,
+            ) -> ::core::result::Result<(), Self::Error> {
+                match (left, right) {
+                    (#value_or_var::Var(left), #value_or_var::Var(right)) => {
+                        unifier.unify_var_var(left, right)
+                    }
+                    (#value_or_var::Var(var), #value_or_var::Value(value))
+                    | (#value_or_var::Value(value), #value_or_var::Var(var)) => {
+                        #occurs
+                        unifier.unify_var_value(var, value)
+                    }
+                    (
+                        #value_or_var::Value(left),
+                        #value_or_var::Value(right),
+                    ) => match (left, right) {
+                        #(#arms)*
+                        #fallback
+                    },
+                }
+            }
+
+            fn merge(
+                left: &Self,
+                right: &Self,
+            ) -> ::core::result::Result<Self, Self::Error> {
+                #merge(left, right)
+            }
+
+            fn normalize(
+                value: #value_or_var<Self>,
+                unifier: &mut #unification::Unifier<Self>,
+            ) -> #value_or_var<Self> {
+                match unifier.normalize(value) {
+                    #value_or_var::Value(value) => {
+                        #value_or_var::Value(match value { #(#normalize)* })
+                    }
+                    var => var,
+                }
+            }
+
+            fn children(
+                &self,
+            ) -> impl ::core::iter::Iterator<Item = &#value_or_var<Self>> {
+                let children: ::std::vec::Vec<&#value_or_var<Self>> =
+                    match self { #(#children)* };
+                children.into_iter()
+            }
+
+            fn children_mut(
+                &mut self,
+            ) -> impl ::core::iter::Iterator<Item = &mut #value_or_var<Self>>
+            {
+                let children: ::std::vec::Vec<&mut #value_or_var<Self>> =
+                    match self { #(#children_mut)* };
+                children.into_iter()
+            }
+        }
+    })
+}
@@ -0,0 +1,177 @@
+//! Combined error reporting for unification and substitution
+
+use std::fmt::{Display, Write};
+
+use crate::substitution::{self, Unresolved};
+
+/// Accumulates the errors from
+/// [`unification::Table::unify_with_recovery`](crate::unification::Table::unify_with_recovery)
+/// and [`substitution::Table::resolve_lenient`] so they can be reported
+/// together
+#[derive(Debug, Default)]
+pub struct Diagnostics(Vec<Diagnostic>);
+
+#[derive(Debug)]
+enum Diagnostic {
+    Conflict(String),
+    Unresolved {
+        vars: Vec<substitution::Var>,
+        cycle: bool,
+        error: Option<String>,
+    },
+}
+
+impl Diagnostics {
+    /// Constructor
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record unification conflicts
+    pub fn conflicts<E: Display>(
+        &mut self,
+        errors: impl IntoIterator<Item = E>,
+    ) {
+        self.0.extend(
+            errors
+                .into_iter()
+                .map(|error| Diagnostic::Conflict(error.to_string())),
+        );
+    }
+
+    /// Record variables which substitution was unable to resolve
+    pub fn unresolved<E: Display>(
+        &mut self,
+        unresolved: impl IntoIterator<Item = Unresolved<E>>,
+    ) {
+        self.0.extend(unresolved.into_iter().map(
+            |Unresolved { vars, cycle, error }| {
+                let mut vars = vars.into_iter().collect::<Vec<_>>();
+                vars.sort_unstable_by_key(|substitution::Var(var)| *var);
+                Diagnostic::Unresolved {
+                    vars,
+                    cycle,
+                    error: error.map(|e| e.to_string()),
+                }
+            },
+        ));
+    }
+
+    /// Check if anything has been recorded
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Render every recorded diagnostic, one per line
+    #[must_use]
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        for diagnostic in &self.0 {
+            let _ = match diagnostic {
+                Diagnostic::Conflict(error) => {
+                    writeln!(report, "unification conflict: {error}")
+                }
+                Diagnostic::Unresolved { vars, cycle, error } => {
+                    let kind = if *cycle { "cycle" } else { "variable" };
+                    let vars = vars
+                        .iter()
+                        .map(|var| format!("{var:?}"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    let error = error
+                        .as_ref()
+                        .map(|error| format!(": {error}"))
+                        .unwrap_or_default();
+                    writeln!(report, "unresolved {kind} {vars}{error}")
+                }
+            };
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Diagnostics;
+    use crate::{
+        substitution::{self, Value},
+        unification::{self, ValueOrVar},
+    };
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("cycle has no base case")]
+    struct NoBaseCase;
+
+    #[derive(Clone)]
+    struct Depth;
+
+    impl Value for Depth {
+        type Error = NoBaseCase;
+
+        fn merge(left: Self, _: Self) -> Result<Self, Self::Error> {
+            Ok(left)
+        }
+
+        fn resolve_cycle(
+            known: Option<Self>,
+            _: usize,
+        ) -> Result<Self, Self::Error> {
+            known.ok_or(NoBaseCase)
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct Unit;
+
+    impl unification::Unify for Unit {
+        type Error = &'static str;
+
+        fn unify(
+            _: ValueOrVar<Self>,
+            _: ValueOrVar<Self>,
+            _: &mut unification::Unifier<Self>,
+        ) -> Result<(), Self::Error> {
+            Err("always conflicts")
+        }
+
+        fn merge(_: &Self, _: &Self) -> Result<Self, Self::Error> {
+            Err("always conflicts")
+        }
+    }
+
+    #[test]
+    fn report() {
+        let mut diagnostics = Diagnostics::new();
+        assert!(diagnostics.is_empty());
+
+        let mut table = unification::Table::<Unit>::new();
+        let a = table.var();
+        table.constraint(ValueOrVar::Var(a), ValueOrVar::Value(Unit));
+        let (_, errors) = table.unify_with_recovery();
+        diagnostics.conflicts(errors);
+
+        let mut table = substitution::Table::<Depth>::new();
+        let a = table.var();
+        let b = table.var();
+        table.dependency(a, b);
+        table.dependency(b, a);
+        let (_, unresolved) = table.resolve_lenient();
+        diagnostics.unresolved(unresolved);
+
+        // A variable depending on itself is still a cycle
+        let mut table = substitution::Table::<Depth>::new();
+        let a = table.var();
+        table.dependency(a, a);
+        let (_, unresolved) = table.resolve_lenient();
+        diagnostics.unresolved(unresolved);
+
+        assert_eq!(
+            diagnostics.report(),
+            "unification conflict: always conflicts\n\
+             unresolved cycle Var(0), Var(1): cycle has no base case\n\
+             unresolved cycle Var(0): cycle has no base case\n"
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! A simple directed graph
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use genawaiter::rc::Gen;
+
+use self::tarjan::Tarjan;
+
+mod tarjan;
+
+/// Directed graph, stored as the set of children of each node
+#[derive(Debug)]
+pub struct Graph<Node>(HashMap<Node, HashSet<Node>>);
+
+impl<Node> Default for Graph<Node> {
+    fn default() -> Self {
+        Self(HashMap::default())
+    }
+}
+
+#[cfg(test)]
+impl Graph<usize> {
+    fn from_edges(edges: impl IntoIterator<Item = (usize, usize)>) -> Self {
+        let mut this = Self::new();
+        for (start, end) in edges {
+            this.add_edge(start, end);
+        }
+        this
+    }
+}
+
+impl<Node: Copy + Hash + Eq> Graph<Node> {
+    /// Constructor
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an edge from `start` to `end`, adding either node if it isn't in
+    /// the graph already
+    pub fn add_edge(&mut self, start: Node, end: Node) {
+        let _ = self.0.entry(start).or_default().insert(end);
+        let _ = self.0.entry(end).or_default();
+    }
+
+    /// Add an edge from `start` to each of `ends`
+    pub fn add_edges(&mut self, start: Node, ends: &HashSet<Node>) {
+        for end in ends {
+            self.add_edge(start, *end);
+        }
+    }
+
+    pub(crate) fn delete_outgoing_edges(&mut self, node: Node) {
+        let _ = self.0.insert(node, HashSet::new());
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Iterate over every node in the graph, in no particular order
+    pub fn nodes(&self) -> impl Iterator<Item = Node> {
+        self.0.keys().copied()
+    }
+
+    /// Iterate over the nodes `node` has an edge to, None if `node` isn't in
+    /// the graph
+    pub fn children(&self, node: Node) -> Option<impl Iterator<Item = Node>> {
+        let children = self.0.get(&node)?;
+        Some(children.iter().copied())
+    }
+
+    /// A copy of the graph with every edge flipped, so each node's children
+    /// are the nodes which had an edge to it
+    ///
+    /// Every node is kept, including those with no edges
+    #[must_use]
+    pub fn reverse(&self) -> Self {
+        let mut reversed =
+            Self(self.0.keys().map(|&node| (node, HashSet::new())).collect());
+        for (&start, ends) in &self.0 {
+            for &end in ends {
+                reversed.add_edge(end, start);
+            }
+        }
+        reversed
+    }
+
+    /// Iterate over the strongly connected components of the graph
+    ///
+    /// Components come out in reverse topological order, every component
+    /// reachable from a component is produced before it
+    ///
+    /// The search only advances as components are pulled from the iterator,
+    /// so stopping early skips the rest of the graph
+    pub fn strongly_connected_components(
+        &self,
+    ) -> impl Iterator<Item = HashSet<Node>> {
+        Gen::new(|co| async move { Tarjan::new(&co, self).tarjan().await })
+            .into_iter()
+    }
+
+    /// True if the graph has any cycles, including a node with an edge to
+    /// itself
+    ///
+    /// Stops searching as soon as a cycle is found rather than finding every
+    /// component
+    #[must_use]
+    pub fn has_cycle(&self) -> bool {
+        self.0
+            .iter()
+            .any(|(node, children)| children.contains(node))
+            || self
+                .strongly_connected_components()
+                .any(|component| component.len() > 1)
+    }
+
+    /// Collapse each [strongly connected
+    /// component](Graph::strongly_connected_components) into a single node,
+    /// giving a graph without cycles
+    ///
+    /// Components are numbered in the order they are produced by
+    /// `strongly_connected_components`, so every edge goes from a higher
+    /// number to a lower one. There is an edge between two components if any
+    /// of their nodes had an edge between them, edges within a component are
+    /// dropped. The map takes each node to its component
+    #[must_use]
+    pub fn condensation(&self) -> (Graph<usize>, HashMap<Node, usize>) {
+        let components = self
+            .strongly_connected_components()
+            .enumerate()
+            .flat_map(|(id, component)| {
+                component.into_iter().map(move |node| (node, id))
+            })
+            .collect::<HashMap<_, _>>();
+        let mut condensed = Graph::new();
+        for (node, ends) in &self.0 {
+            let start = components[node];
+            // Every member of a component adds its edges to the same node,
+            // so the node is only created once rather than cleared per member
+            let _ = condensed.0.entry(start).or_default();
+            for end in ends {
+                let end = components[end];
+                if start != end {
+                    condensed.add_edge(start, end);
+                }
+            }
+        }
+        (condensed, components)
+    }
+}
+
+impl<Node: Copy + Hash + Eq> IntoIterator for Graph<Node> {
+    type Item = (Node, HashSet<Node>);
+
+    type IntoIter = <HashMap<Node, HashSet<Node>> as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        collections::HashSet,
+        hash::{Hash, Hasher},
+    };
+
+    use super::Graph;
+
+    macro_rules! set {
+        ($($items: expr),* $(,)?) => {
+            std::collections::HashSet::from([$($items),*])
+        }
+    }
+
+    #[test]
+    fn nodes() {
+        let graph = Graph::from_edges([(0, 1), (0, 2), (0, 3)]);
+        let nodes = graph.nodes().collect::<HashSet<_>>();
+        assert_eq!(nodes, set! {0, 1, 2, 3});
+    }
+
+    #[test]
+    fn children() {
+        let graph = Graph::from_edges([(0, 1), (0, 2), (0, 3)]);
+        assert_eq!(
+            graph.children(0).map(Iterator::collect),
+            Some(set! {1, 2, 3})
+        );
+        assert_eq!(graph.children(1).map(Iterator::collect), Some(set! {}));
+        assert_eq!(graph.children(2).map(Iterator::collect), Some(set! {}));
+        assert_eq!(graph.children(3).map(Iterator::collect), Some(set! {}));
+        assert!(graph.children(4).is_none());
+    }
+
+    #[test]
+    fn reverse() {
+        let mut graph = Graph::from_edges([(0, 1), (0, 2), (1, 2), (3, 3)]);
+        // A node with no edges
+        graph.delete_outgoing_edges(4);
+        let reversed = graph.reverse();
+        assert_eq!(reversed.children(0).map(Iterator::collect), Some(set! {}));
+        assert_eq!(reversed.children(1).map(Iterator::collect), Some(set! {0}));
+        assert_eq!(
+            reversed.children(2).map(Iterator::collect),
+            Some(set! {0, 1})
+        );
+        assert_eq!(reversed.children(3).map(Iterator::collect), Some(set! {3}));
+        assert_eq!(reversed.children(4).map(Iterator::collect), Some(set! {}));
+    }
+
+    #[test]
+    fn has_cycle() {
+        assert!(!Graph::from_edges([(0, 1), (0, 2), (1, 2)]).has_cycle());
+        assert!(Graph::from_edges([(0, 1), (1, 2), (2, 0)]).has_cycle());
+        assert!(Graph::from_edges([(0, 1), (1, 1)]).has_cycle());
+    }
+
+    #[test]
+    fn condensation() {
+        let graph = Graph::from_edges([
+            // A cycle of 0 and 1 which depends on 2 twice
+            (0, 1),
+            (1, 0),
+            (0, 2),
+            (1, 2),
+            (3, 3),
+        ]);
+        let (condensed, components) = graph.condensation();
+        assert_eq!(components[&0], components[&1]);
+        let [cycle, leaf, lone] = [0, 2, 3].map(|node| components[&node]);
+        assert_eq!(
+            condensed.children(cycle).map(Iterator::collect),
+            Some(set! {leaf})
+        );
+        assert_eq!(
+            condensed.children(leaf).map(Iterator::collect),
+            Some(set! {})
+        );
+        assert_eq!(
+            condensed.children(lone).map(Iterator::collect),
+            Some(set! {})
+        );
+        assert_eq!(condensed.size(), 3);
+
+        // Only one member of the cycle has an edge leaving it, whichever order
+        // the members are visited in
+        let graph = Graph::from_edges([(0, 1), (1, 0), (1, 2), (2, 3)]);
+        let (condensed, components) = graph.condensation();
+        let [cycle, middle, leaf] = [0, 2, 3].map(|node| components[&node]);
+        assert_eq!(
+            condensed.children(cycle).map(Iterator::collect),
+            Some(set! {middle})
+        );
+        assert_eq!(
+            condensed.children(middle).map(Iterator::collect),
+            Some(set! {leaf})
+        );
+    }
+
+    thread_local! {
+        static HASHES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    // Counts how often it is hashed, as a measure of how much of the graph has
+    // been searched
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Counted(usize);
+
+    impl Hash for Counted {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            HASHES.set(HASHES.get() + 1);
+            self.0.hash(state);
+        }
+    }
+
+    #[test]
+    fn strongly_connected_components_lazy() {
+        let mut graph = Graph::new();
+        for node in 0..100 {
+            graph.add_edge(Counted(node), Counted(node));
+        }
+
+        HASHES.set(0);
+        let mut components = graph.strongly_connected_components();
+        assert!(components.next().is_some());
+        let first = HASHES.get();
+        assert_eq!(99, components.count());
+        let total = HASHES.get();
+        assert!(first * 10 < total, "{first} of {total} hashes to start");
+    }
+
+    #[test]
+    fn strongly_connected_components() {
+        let graph = Graph::from_edges([
+            // A square with corners 0, 1, 2, 3
+            (0, 1),
+            (1, 2),
+            (2, 3),
+            (3, 0),
+            // A triangle with corners 4, 5, 6
+            (4, 5),
+            (5, 6),
+            (6, 4),
+            // A single directed edge connecting the two
+            (4, 3),
+        ]);
+        let components =
+            graph.strongly_connected_components().collect::<Vec<_>>();
+        assert_eq!(components, vec![set! {0, 1, 2, 3}, set! {4, 5, 6}]);
+    }
+}
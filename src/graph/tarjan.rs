@@ -102,7 +102,7 @@ mod tests {
     use genawaiter::rc::Gen;
 
     use super::Tarjan;
-    use crate::substitution::graph::Graph;
+    use crate::graph::Graph;
 
     fn make_graph() -> Graph<usize> {
         Graph::from_edges([
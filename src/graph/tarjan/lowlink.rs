@@ -116,7 +116,7 @@ impl Lowlink {
 #[cfg(test)]
 mod tests {
     use super::{Lowlink, Root};
-    use crate::substitution::graph::tarjan::Index;
+    use crate::graph::tarjan::Index;
 
     #[test]
     fn happy() {
@@ -81,7 +81,7 @@ impl Inner {
 #[cfg(test)]
 mod tests {
     use super::Stack;
-    use crate::substitution::graph::tarjan::Index;
+    use crate::graph::tarjan::Index;
 
     #[test]
     fn push_and_contains() {
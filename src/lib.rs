@@ -19,5 +19,9 @@
 #![allow(clippy::struct_field_names)]
 #![allow(clippy::missing_errors_doc)]
 
+pub use self::diagnostics::Diagnostics;
+
+mod diagnostics;
+pub mod graph;
 pub mod substitution;
 pub mod unification;
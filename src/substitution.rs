@@ -1,19 +1,35 @@
 //! Iterative substitution table
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    fmt,
+    marker::PhantomData,
+};
 
 use value_type::value_type;
 
-use self::graph::Graph;
+use self::resolve::{ByOrdering, ByValue, Resolution, Strategy, Stream};
+pub use self::{
+    incremental::Resolved,
+    resolve::{PartialView, Step},
+};
 
-mod graph;
+mod incremental;
+mod resolve;
 #[cfg(test)]
 mod tests;
 
 /// Variable representing a table entry, used for recording [facts](Table::fact)
 /// and adding [dependency](Table::dependency) relationships
+///
+/// With the `serde` feature variables can be serialized. A deserialized
+/// variable only refers to the same variable in a table which has created the
+/// same variables, in the same order, so persist the table's inputs too
 #[value_type(Copy)]
-pub struct Var(usize);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Var(pub(crate) usize);
 
 /// Value in the table
 ///
@@ -27,9 +43,70 @@ pub trait Value: Sized {
     /// Called to merge the values of dependencies to produce a value for a row
     fn merge(left: Self, right: Self) -> Result<Self, Self::Error>;
 
-    /// Called if a cyclic dependency is detected. The parameter is the partial
-    /// result not counting the row itself
-    fn resolve_cycle(known: Option<Self>) -> Result<Self, Self::Error>;
+    /// Like [`merge`](Value::merge) but also told which variables are
+    /// involved, e.g. to report where conflicting values came from
+    ///
+    /// This is what resolution calls, the default implementation ignores the
+    /// context and calls [`merge_or_skip`](Value::merge_or_skip)
+    fn merge_with_context(
+        left: Self,
+        right: Self,
+        context: MergeContext,
+    ) -> Result<Self, Self::Error> {
+        let _ = context;
+        Self::merge_or_skip(left, right).map(MergeOutcome::into_inner)
+    }
+
+    /// Like [`merge`](Value::merge) but can ignore `right` rather than fail,
+    /// e.g. for a best effort join where values which don't combine shouldn't
+    /// stop resolution
+    ///
+    /// The default implementation calls [`merge`](Value::merge)
+    fn merge_or_skip(
+        left: Self,
+        right: Self,
+    ) -> Result<MergeOutcome<Self>, Self::Error> {
+        Self::merge(left, right).map(MergeOutcome::Merged)
+    }
+
+    /// Called if a cyclic dependency is detected. `known` is the partial
+    /// result not counting the cycle itself and `cycle_size` is the number of
+    /// variables in the cycle, 1 for a variable which isn't part of a larger
+    /// one
+    fn resolve_cycle(
+        known: Option<Self>,
+        cycle_size: usize,
+    ) -> Result<Self, Self::Error>;
+}
+
+/// Returned by [`Value::merge_or_skip`]
+#[value_type]
+pub enum MergeOutcome<T> {
+    /// The two values were merged into this one
+    Merged(T),
+    /// The right hand value was ignored, leaving this one
+    Keep(T),
+}
+
+impl<T> MergeOutcome<T> {
+    /// The resulting value, whichever way it was produced
+    pub fn into_inner(self) -> T {
+        match self {
+            MergeOutcome::Merged(value) | MergeOutcome::Keep(value) => value,
+        }
+    }
+}
+
+/// The variables involved in a call to [`Value::merge_with_context`]
+#[value_type(Copy)]
+pub struct MergeContext {
+    /// The variable being resolved
+    pub target: Var,
+    /// The dependency the left hand value came from. If the value is already
+    /// the result of earlier merges this is the first dependency merged
+    pub left: Var,
+    /// The dependency the right hand value came from, as for `left`
+    pub right: Var,
 }
 
 /// Returned by [`Table::fact`] if it is called twice with the same [`Var`]
@@ -38,23 +115,230 @@ pub trait Value: Sized {
 #[error("Duplicate entry for {0:?} in facts table")]
 pub struct DuplicateFactError(pub Var);
 
+/// Returned by [`Table::try_dependency`] if a variable is made to depend on
+/// itself and the table's [policy](SelfDependencyPolicy) is
+/// [`Error`](SelfDependencyPolicy::Error)
+#[value_type(Copy)]
+#[derive(thiserror::Error)]
+#[error("{0:?} depends on itself")]
+pub struct SelfDependencyError(pub Var);
+
+/// Returned by [`Table::build_plan`] if the dependencies contain a cycle
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Dependency cycle between {} variables", .0.len())]
+pub struct CycleError(pub HashSet<Var>);
+
+/// How [`Table::dependency`] treats a variable depending directly on itself,
+/// see [`Table::with_self_dependency_policy`]
+#[value_type(Copy)]
+#[derive(Default)]
+pub enum SelfDependencyPolicy {
+    /// Record the dependency, the variable is resolved as a cycle
+    #[default]
+    Allow,
+    /// Drop the dependency
+    Ignore,
+    /// Reject the dependency with a [`SelfDependencyError`]
+    Error,
+}
+
 /// Error returned by [`Table::resolve`]
 #[derive(Debug, thiserror::Error)]
 pub enum Error<E: std::error::Error> {
     /// Returned if the substitution process ceases to make progress
-    #[error("Substitution stopped making progress")]
-    NoProgress,
-    /// Wraps [`Value::Error`]
+    #[error(
+        "Substitution stopped making progress, {}",
+        summarize(.stuck, .labels)
+    )]
+    NoProgress {
+        /// The variables which were still unresolved, each mapped to the
+        /// variables it was still waiting on
+        stuck: HashMap<Var, HashSet<Var>>,
+        /// Labels of any of the variables above created with
+        /// [`Table::var_labeled`]
+        labels: HashMap<Var, String>,
+    },
+    /// Returned by [`Table::resolve_bounded`] if resolution is still
+    /// incomplete after the maximum number of passes
+    #[error("Substitution still incomplete after {passes} passes")]
+    IterationLimit {
+        /// The number of passes made
+        passes: usize,
+    },
+    /// Wraps [`Value::Error`] raised by [`Value::merge_with_context`], or by
+    /// [`Value::merge`] in [`Table::fact_checked`]
     #[error(transparent)]
     Custom(#[from] E),
+    /// Wraps [`Value::Error`] raised by [`Value::resolve_cycle`]
+    #[error("Failed to resolve the cycle containing {var:?}: {source}")]
+    Cycle {
+        /// The variable whose cycle failed to resolve
+        var: Var,
+        /// The error
+        source: E,
+    },
+}
+
+impl<E: std::error::Error> Error<E> {
+    // Resolution doesn't know about labels, they're filled in afterwards by
+    // the table
+    fn labeled(mut self, all: &HashMap<Var, String>) -> Self {
+        if let Self::NoProgress { stuck, labels } = &mut self {
+            labels.extend(
+                stuck
+                    .iter()
+                    .flat_map(|(var, waiting)| {
+                        std::iter::once(var).chain(waiting)
+                    })
+                    .filter_map(|var| Some((*var, all.get(var)?.clone()))),
+            );
+        }
+        self
+    }
+}
+
+/// Variables which [`Table::resolve_lenient`] was unable to resolve
+#[derive(Debug)]
+pub struct Unresolved<E> {
+    /// The affected variables, either a single variable or a cycle of
+    /// mutually dependent variables
+    pub vars: HashSet<Var>,
+    /// Whether the variables form a cycle, i.e. there is more than one or the
+    /// only one depends on itself
+    pub cycle: bool,
+    /// The error raised while resolving the variables, None if resolution
+    /// stalled without an error
+    pub error: Option<E>,
+}
+
+/// Outcome of [`Table::resolve_single_pass`]
+#[derive(Debug)]
+pub struct SinglePassResult<T: Value> {
+    /// Facts and any variables resolved by the pass
+    pub resolved: HashMap<Var, T>,
+    /// The remaining variables, each mapped to the variables it is still
+    /// waiting on
+    pub residual: HashMap<Var, HashSet<Var>>,
+    /// Variables which failed to resolve, these are in neither of the other
+    /// maps
+    pub errors: HashMap<Var, T::Error>,
+}
+
+// Renumbered values and the mapping from the original variables, see
+// Table::resolve_renumbered
+type Renumbered<T> = (HashMap<Var, T>, HashMap<Var, Var>);
+
+// Resolved values and the variables resolved by breaking a cycle, see
+// Table::resolve_with_provenance
+type WithProvenance<T> = (HashMap<Var, T>, HashSet<Var>);
+// See Table::resolve_with_cycles
+type WithCycles<T> = (HashMap<Var, T>, Vec<Vec<Var>>);
+
+// Components can come out in any order
+fn same_components(left: &[HashSet<Var>], right: &[HashSet<Var>]) -> bool {
+    left.len() == right.len()
+        && left.iter().all(|component| right.contains(component))
+}
+
+fn summarize(
+    stuck: &HashMap<Var, HashSet<Var>>,
+    labels: &HashMap<Var, String>,
+) -> String {
+    const EXAMPLES: usize = 3;
+    let mut vars = stuck.keys().copied().collect::<Vec<_>>();
+    vars.sort_unstable_by_key(|Var(var)| *var);
+    let examples = vars
+        .iter()
+        .take(EXAMPLES)
+        .map(|var| match labels.get(var) {
+            Some(label) => format!("{var:?} {label:?}"),
+            None => format!("{var:?}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let more = if vars.len() > EXAMPLES { ", ..." } else { "" };
+    format!("{} variables unresolved ({examples}{more})", vars.len())
+}
+
+/// The strongly connected components of a [`Table`]'s dependency graph,
+/// produced by [`Table::condense`]
+#[derive(Debug)]
+pub struct CondensedTable<T> {
+    components: Vec<HashSet<Var>>,
+    table: PhantomData<fn() -> T>,
+}
+
+impl<T> CondensedTable<T> {
+    /// The strongly connected components, see
+    /// [`Table::dependency_components`]
+    #[must_use]
+    pub fn components(&self) -> &[HashSet<Var>] {
+        &self.components
+    }
 }
 
 /// Iterative substitution table
-#[expect(missing_debug_implementations)]
 pub struct Table<T> {
     next_var: usize,
     known: HashMap<Var, T>,
     unknown: HashMap<Var, HashSet<Var>>,
+    // Dependencies of variables in known, kept so they can be restored if the
+    // fact is retracted
+    shadowed: HashMap<Var, HashSet<Var>>,
+    // Only recorded for tables created with Table::with_undo
+    undo_log: Option<Vec<Operation<T>>>,
+    self_dependencies: SelfDependencyPolicy,
+    // Labels given by Table::var_labeled
+    labels: HashMap<Var, String>,
+}
+
+/// A change to the table which can be reversed by [`Table::undo`]
+enum Operation<T> {
+    Var,
+    Fact(Var),
+    // Holds the value the fact had before it was merged
+    Merge(Var, T),
+    Dependency(Var, Var),
+}
+
+impl<T: fmt::Debug> fmt::Debug for Table<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Sorted so the output doesn't depend on hash order
+        let mut facts = self.facts().collect::<Vec<_>>();
+        facts.sort_unstable_by_key(|(Var(var), _)| *var);
+        let mut dependencies = self
+            .dependencies()
+            .map(|(var, dependencies)| {
+                let mut dependencies =
+                    dependencies.iter().copied().collect::<Vec<_>>();
+                dependencies.sort_unstable_by_key(|Var(var)| *var);
+                (var, dependencies)
+            })
+            .collect::<Vec<_>>();
+        dependencies.sort_unstable_by_key(|(Var(var), _)| *var);
+        let mut debug = f.debug_struct("Table");
+        let _ = debug
+            .field("vars", &self.next_var)
+            .field("facts", &DebugMap(facts))
+            .field("dependencies", &DebugMap(dependencies));
+        if !self.labels.is_empty() {
+            let mut labels = self.labels.iter().collect::<Vec<_>>();
+            labels.sort_unstable_by_key(|(Var(var), _)| *var);
+            let _ = debug.field("labels", &DebugMap(labels));
+        }
+        debug.finish_non_exhaustive()
+    }
+}
+
+// Formats pairs as a map without requiring the keys to be hashable or ordered
+struct DebugMap<K, V>(Vec<(K, V)>);
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for DebugMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(k, v)| (k, v)))
+            .finish()
+    }
 }
 
 impl<T> Default for Table<T> {
@@ -63,24 +347,89 @@ impl<T> Default for Table<T> {
             next_var: 0,
             known: HashMap::new(),
             unknown: HashMap::new(),
+            shadowed: HashMap::new(),
+            undo_log: None,
+            self_dependencies: SelfDependencyPolicy::default(),
+            labels: HashMap::new(),
         }
     }
 }
 
-impl<T: Clone> Table<T> {
+impl<T> Table<T> {
     /// Constructor
     #[must_use]
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Construct a table which records changes so they can be reversed with
+    /// [`Table::undo`]
+    #[must_use]
+    pub fn with_undo() -> Self {
+        Self {
+            undo_log: Some(Vec::new()),
+            ..Self::default()
+        }
+    }
+
+    /// Set how the table treats a variable depending directly on itself
+    #[must_use]
+    pub fn with_self_dependency_policy(
+        mut self,
+        policy: SelfDependencyPolicy,
+    ) -> Self {
+        self.self_dependencies = policy;
+        self
+    }
+
+    /// Construct a table with space reserved for `vars` variables, see
+    /// [`Table::reserve`]
+    #[must_use]
+    pub fn with_capacity(vars: usize) -> Self {
+        let mut table = Self::new();
+        table.reserve(vars);
+        table
+    }
+
+    /// Reserve space for at least `additional` more variables
+    pub fn reserve(&mut self, additional: usize) {
+        self.known.reserve(additional);
+        self.unknown.reserve(additional);
+    }
+
     /// Produce a new [`Var`]
     pub fn var(&mut self) -> Var {
         let var = Var(self.next_var);
         self.next_var += 1;
+        self.record(Operation::Var);
+        var
+    }
+
+    /// Produce a new [`Var`] with a label, used in place of the bare variable
+    /// in [`Error::NoProgress`] messages and the table's [`Debug`](fmt::Debug)
+    /// output
+    pub fn var_labeled(&mut self, label: impl Into<String>) -> Var {
+        let var = self.var();
+        let _ = self.labels.insert(var, label.into());
         var
     }
 
+    /// The number of variables produced so far
+    ///
+    /// Variables are numbered densely from zero, so this is also one past the
+    /// index of the most recent variable. [Undoing](Table::undo) a variable
+    /// reduces the count
+    #[must_use]
+    pub fn var_count(&self) -> usize {
+        self.next_var
+    }
+
+    /// The label `var` was created with, if any
+    #[must_use]
+    pub fn label(&self, var: Var) -> Option<&str> {
+        self.labels.get(&var).map(String::as_str)
+    }
+
     /// Record a known fact in the table
     ///
     /// Facts supercede dependencies e.g all of the following are equivalent
@@ -93,7 +442,7 @@ impl<T: Clone> Table<T> {
     /// #     fn merge(_: Self, _: Self) -> Result<Self, Self::Error> {
     /// #         Ok(SomeValue)
     /// #     }
-    /// #     fn resolve_cycle(_: Option<Self>) -> Result<Self, Self::Error> {
+    /// #     fn resolve_cycle(_: Option<Self>, _: usize) -> Result<Self, Self::Error> {
     /// #         Ok(SomeValue)
     /// #     }
     /// # }
@@ -124,8 +473,11 @@ impl<T: Clone> Table<T> {
         let _ = self.known.insert(var, value);
 
         // Entries in known supercede entries in unknown
-        let _ = self.unknown.remove(&var);
+        if let Some(dependencies) = self.unknown.remove(&var) {
+            let _ = self.shadowed.insert(var, dependencies);
+        }
 
+        self.record(Operation::Fact(var));
         Ok(())
     }
 
@@ -141,7 +493,7 @@ impl<T: Clone> Table<T> {
     /// #     fn merge(_: Self, _: Self) -> Result<Self, Self::Error> {
     /// #         Ok(SomeValue)
     /// #     }
-    /// #     fn resolve_cycle(_: Option<Self>) -> Result<Self, Self::Error> {
+    /// #     fn resolve_cycle(_: Option<Self>, _: usize) -> Result<Self, Self::Error> {
     /// #         Ok(SomeValue)
     /// #     }
     /// # }
@@ -161,249 +513,706 @@ impl<T: Clone> Table<T> {
     /// let mut table = Table::default();
     /// table.fact(a, SomeValue).unwrap();
     /// ```
+    ///
+    /// # Panics
+    /// If `var` and `depends_on` are the same variable and the table's
+    /// [policy](Table::with_self_dependency_policy) is
+    /// [`Error`](SelfDependencyPolicy::Error)
     pub fn dependency(&mut self, var: Var, depends_on: Var) {
+        self.try_dependency(var, depends_on)
+            .unwrap_or_else(|e| panic!("{e}"));
+    }
+
+    /// Add a dependency to the table, failing if `var` and `depends_on` are
+    /// the same variable and the table's
+    /// [policy](Table::with_self_dependency_policy) is
+    /// [`Error`](SelfDependencyPolicy::Error)
+    pub fn try_dependency(
+        &mut self,
+        var: Var,
+        depends_on: Var,
+    ) -> Result<(), SelfDependencyError> {
+        if var == depends_on {
+            match self.self_dependencies {
+                SelfDependencyPolicy::Allow => (),
+                SelfDependencyPolicy::Ignore => return Ok(()),
+                SelfDependencyPolicy::Error => {
+                    return Err(SelfDependencyError(var));
+                }
+            }
+        }
+
         // Entries in known supercede entries in unknown
-        if self.known.contains_key(&var) {
-            return;
+        let table = if self.known.contains_key(&var) {
+            &mut self.shadowed
+        } else {
+            &mut self.unknown
+        };
+        if table.entry(var).or_default().insert(depends_on) {
+            self.record(Operation::Dependency(var, depends_on));
         }
-        let _ = self.unknown.entry(var).or_default().insert(depends_on);
+        Ok(())
     }
 
-    /// Resolve the declared dependencies in the table
-    pub fn resolve(self) -> Result<HashMap<Var, T>, Error<T::Error>>
-    where
-        T: Value,
-    {
-        // This is the table of resolved information, the goal is to move all of
-        // the variables into this table. We start by populating it with our
-        // initial set of facts
-        let mut complete = self.known;
-        // Partials holds the partial inference results
-        let mut partials = Self::prepare_partials(self.unknown);
-        // For unresolved partials in the loop below
-        let mut next = HashMap::with_capacity(partials.len());
-
-        // Loop until we run out of partials
-        while !partials.is_empty() {
-            let mut progress = false;
-
-            // Check each currently unresolved variable
-            for (var, partial) in partials {
-                if complete.contains_key(&var) {
+    /// Split the table into its facts and the variables which still need to
+    /// be resolved, for driving resolution manually
+    ///
+    /// Each [`PartialView`] can be stepped with [`PartialView::try_step`]
+    /// using the variables resolved so far, starting from the facts. Cycles
+    /// have already been collapsed so every variable in a cycle depends
+    /// directly on itself
+    #[must_use]
+    pub fn into_partials(
+        self,
+    ) -> (HashMap<Var, T>, HashMap<Var, PartialView<T>>) {
+        let partials = resolve::partial_views(&self.known, &self.unknown);
+        (self.known, partials)
+    }
+
+    /// Group the variables mentioned by dependencies into strongly connected
+    /// components
+    ///
+    /// Variables in the same component depend on each other, possibly
+    /// indirectly. Variables which aren't part of a cycle are returned in a
+    /// component of their own. Dependencies superceded by a fact are ignored
+    #[must_use]
+    pub fn dependency_components(&self) -> Vec<HashSet<Var>> {
+        resolve::condense(&self.unknown)
+    }
+
+    /// The [components](Table::dependency_components) of the dependency graph
+    /// ordered so that each comes after every component it depends on
+    ///
+    /// Variables without any dependencies of their own come first, as
+    /// singletons in ascending order
+    #[must_use]
+    pub fn topological_order(&self) -> Vec<HashSet<Var>> {
+        // Components already come out with dependencies first, leaves can be
+        // moved to the front without breaking that
+        let (mut leaves, rest): (Vec<_>, Vec<_>) =
+            resolve::condense(&self.unknown).into_iter().partition(
+                |component| {
+                    component.iter().all(|var| !self.unknown.contains_key(var))
+                },
+            );
+        leaves.sort_unstable_by_key(|component| {
+            component.iter().map(|Var(var)| *var).min()
+        });
+        leaves.extend(rest);
+        leaves
+    }
+
+    /// Compute the strongly connected components of the dependency graph
+    /// ahead of time, see [`Table::resolve_from_condensation`]
+    #[must_use]
+    pub fn condense(&self) -> CondensedTable<T> {
+        CondensedTable {
+            components: resolve::condense(&self.unknown),
+            table: PhantomData,
+        }
+    }
+
+    /// Order the variables without facts so that each comes after everything
+    /// it depends on, e.g. to compute them one at a time outside of the table
+    ///
+    /// Each variable is paired with its dependencies in ascending order. Fails
+    /// with the variables of one of the cycles if there are any
+    pub fn build_plan(&self) -> Result<Vec<(Var, Vec<Var>)>, CycleError> {
+        let mut plan = Vec::new();
+        // Components come out with dependencies before their dependents
+        for component in resolve::condense(&self.unknown) {
+            let cyclic = component.len() > 1
+                || component.iter().any(|var| {
+                    self.unknown
+                        .get(var)
+                        .is_some_and(|dependencies| dependencies.contains(var))
+                });
+            if cyclic {
+                return Err(CycleError(component));
+            }
+            for var in component {
+                if self.known.contains_key(&var) {
                     continue;
                 }
-                // Attempt to progress the partial result with respect to what
-                // we know so far
-                match partial.try_resolve(&complete)? {
-                    TryResolveResult::Complete(result) => {
-                        // If we resolved all of our dependencies record the
-                        // result in the completed table and mark that we made
-                        // progress
-                        let _ = complete.insert(var, result);
-                        progress = true;
-                    }
-                    TryResolveResult::Incomplete(partial, progressed) => {
-                        // If we still have outstanding dependencies we store
-                        // the new partial in the next table. In this case
-                        // try_resolve also tells us if we managed to learn
-                        // anything new this pass so record that too
-                        let _ = next.insert(var, partial);
-                        progress = progress || progressed;
-                    }
-                }
+                let mut dependencies = self
+                    .unknown
+                    .get(&var)
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .collect::<Vec<_>>();
+                dependencies.sort_unstable_by_key(|Var(var)| *var);
+                plan.push((var, dependencies));
             }
-
-            // If we made no progress, bail
-            if !progress {
-                return Err(Error::NoProgress);
-            }
-
-            // We've been putting anything unresolved in the next table, swap
-            // that into the active one and drain the formerly active one
-            partials = next;
-            next = HashMap::with_capacity(partials.len());
         }
+        Ok(plan)
+    }
 
-        Ok(complete)
-    }
-
-    // The major point of this and the reason we can't just use the original
-    // unknown table directly for resolution has to do with cycles in the
-    // dependency graph.
-    //
-    // We start by finding all of the strongly connected components in the
-    // dependency graph, this is a slightly wider condition than true cycles
-    //
-    // Consider a single strongly connected component with no incomming or
-    // outgoing edges. By the definition of a strongly connected component every
-    // node in the component is reachable by some path from every other node.
-    // Since our edges are dependencies that means every node in the component
-    // is (transitivley) dependent on every other node in the component and
-    // ultimatly on itself. As there are no other outgoing nodes there is no
-    // extra information to be had and every node in the component must resolve
-    // to the same value in the event we can decide what value that should be.
-    // So we can replace the entire component with a single 'virtual' node with
-    // one dependency edge pointing back at itself, this is a situation we know
-    // how to resolve.
-    //
-    // Now consider a component with some number of outgoing edges (e.g some
-    // nodes inside the component depend on some nodes outside of the
-    // component). Because any node inside the component reachable from any
-    // other node then there is a path from every node inside the component to
-    // every non-component node depended on by any node in the component. So, to
-    // labor the point, every node in the component (transitivley) depends on
-    // every non-component node depended on by any component node. Similar to
-    // the first case we can achive the same affect by collapsing the entire
-    // component into a single virtual node with a recursive dependency & a
-    // dependency on each of the non-component nodes dependended on by any node
-    // in the component.
-    //
-    // Finally consider incoming dependency edges. After collapsing the
-    // component into a single node we could go through the entire graph and
-    // patch up any dependency edge targeting any component node to refer
-    // instead to the virtual node. This is awkward though since we index edges
-    // by source node so we have to traverse every other edge in the graph in
-    // order to find edges which travel into the component. Since the premise of
-    // the virtual node transformation is that every node in the component is
-    // essentially identical we can instead make very component node look like
-    // the proposed virtual node (e.g with a direct dependency for each edge
-    // leaving the component and one recursive dependency edge). This has the
-    // same affect as the virtual node approach but means we don't need to patch
-    // up incoming edges or translate the virtual node(s) back to the original
-    // nodes after inference
-    fn prepare_partials(
-        unknown: HashMap<Var, HashSet<Var>>,
-    ) -> HashMap<Var, Partial<T>> {
-        let mut graph = Graph::new();
-        for (src, dsts) in unknown {
-            graph.add_edges(src, &dsts);
+    /// Estimate the number of bytes held by the table
+    ///
+    /// This counts the space reserved by the internal maps, the undo log and
+    /// the labels, including space for the values themselves, but not any heap
+    /// allocations owned by the values
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        fn reserved<K, V>(map: &HashMap<K, V>) -> usize {
+            map.capacity() * size_of::<(K, V)>()
         }
 
-        // Compute all of the strongly connected components of the graph
-        let sccs = graph.strongly_connected_components().collect::<Vec<_>>();
+        let dependencies = self
+            .unknown
+            .values()
+            .chain(self.shadowed.values())
+            .map(|dependencies| dependencies.capacity() * size_of::<Var>())
+            .sum::<usize>();
+        let undo_log = self
+            .undo_log
+            .as_ref()
+            .map_or(0, |log| log.capacity() * size_of::<Operation<T>>());
+        let labels = self.labels.values().map(String::capacity).sum::<usize>();
+        size_of::<Self>()
+            + reserved(&self.known)
+            + reserved(&self.unknown)
+            + reserved(&self.shadowed)
+            + reserved(&self.labels)
+            + dependencies
+            + undo_log
+            + labels
+    }
 
-        // For each of them
-        for component in sccs {
-            // Compute the set of dependencies of the component, this is the
-            // union of all of the dependencies of all of the nodes in the
-            // component minus any nodes which are themselves members of the
-            // component
-            let all_dependencies = component
-                .iter()
-                .filter_map(|&node| graph.children(node))
-                .flatten()
-                .filter(|node| !component.contains(node))
-                .collect();
-            // For each node in the component we delete all of the original
-            // edges it had and add one for each of the components dependencies
-            // and one recursive edge
-            for node in component {
-                graph.delete_outgoing_edges(node);
-                graph.add_edges(node, &all_dependencies);
-                graph.add_edge(node, node);
+    /// Iterate over the facts recorded in the table
+    pub fn facts(&self) -> impl Iterator<Item = (Var, &T)> {
+        self.known.iter().map(|(&var, value)| (var, value))
+    }
+
+    /// Iterate over the dependencies recorded in the table
+    ///
+    /// Dependencies of variables which also have a fact are superceded by the
+    /// fact and are not included
+    pub fn dependencies(&self) -> impl Iterator<Item = (Var, &HashSet<Var>)> {
+        self.unknown
+            .iter()
+            .map(|(&var, dependencies)| (var, dependencies))
+    }
+
+    /// Reverse the most recent call to [`var`](Table::var),
+    /// [`fact`](Table::fact), [`fact_merge`](Table::fact_merge) or
+    /// [`dependency`](Table::dependency) which changed the table
+    ///
+    /// Returns false if there was nothing to undo. Changes are only recorded
+    /// for tables created with [`Table::with_undo`]
+    pub fn undo(&mut self) -> bool {
+        let Some(operation) = self.undo_log.as_mut().and_then(Vec::pop) else {
+            return false;
+        };
+        match operation {
+            Operation::Var => {
+                self.next_var -= 1;
+                let _ = self.labels.remove(&Var(self.next_var));
+            }
+            Operation::Fact(var) => {
+                let _ = self.retract(var);
+            }
+            Operation::Merge(var, value) => {
+                let _ = self.known.insert(var, value);
+            }
+            Operation::Dependency(var, depends_on) => {
+                let _ = self.remove_dependency(var, depends_on);
             }
         }
+        true
+    }
 
-        // Now we can build our partials table
-        let mut result = HashMap::new();
-        for (var, mut dependencies) in graph {
-            let recursive = dependencies.remove(&var);
-            let _ = result.insert(
-                var,
-                Partial {
-                    recursive,
-                    result: None,
-                    dependencies,
-                },
-            );
+    /// Remove a dependency recorded by [`dependency`](Table::dependency),
+    /// returning false if there was no such dependency
+    ///
+    /// Removing a dependency is not recorded for [`undo`](Table::undo)
+    pub fn remove_dependency(&mut self, var: Var, depends_on: Var) -> bool {
+        // Dependencies superceded by a fact are kept separately
+        let table = if self.known.contains_key(&var) {
+            &mut self.shadowed
+        } else {
+            &mut self.unknown
+        };
+        let Some(dependencies) = table.get_mut(&var) else {
+            return false;
+        };
+        let removed = dependencies.remove(&depends_on);
+        if dependencies.is_empty() {
+            let _ = table.remove(&var);
         }
+        removed
+    }
 
-        result
+    /// Remove a fact from the table, returning its value if there was one
+    ///
+    /// Any dependencies of the variable which were superceded by the fact take
+    /// effect again. This has no effect on the results of any earlier call to
+    /// [`resolve`](Table::resolve), which consumes the table. Retracting a fact
+    /// is not recorded for [`undo`](Table::undo)
+    pub fn retract(&mut self, var: Var) -> Option<T> {
+        let value = self.known.remove(&var)?;
+        if let Some(dependencies) = self.shadowed.remove(&var) {
+            let _ = self.unknown.insert(var, dependencies);
+        }
+        Some(value)
     }
-}
 
-/// Partial result during inference
-struct Partial<T> {
-    // True if the variable assigned to this partial depends on itself
-    recursive: bool,
-    // Partial result, if known
-    result: Option<T>,
-    // Remaining dependencies, if any
-    dependencies: HashSet<Var>,
+    fn record(&mut self, operation: Operation<T>) {
+        if let Some(log) = &mut self.undo_log {
+            log.push(operation);
+        }
+    }
 }
 
-enum TryResolveResult<T> {
-    Complete(T),
-    Incomplete(Partial<T>, bool),
-}
+// Every resolved value is returned in the result as well as being merged into
+// each of its dependents, so resolution needs one copy per dependent on top of
+// the one it returns. Only resolve_var and fact_checked, which return a single
+// value, can move a value into its last dependent instead of cloning it, see
+// Resolution::keep_only. fact_merge also clones so the existing fact survives a
+// failed merge
+impl<T: Clone> Table<T> {
+    /// Record a known fact in the table, merging it with any existing fact for
+    /// the same variable using [`Value::merge`]
+    ///
+    /// Supercedes dependencies in the same way as [`fact`](Table::fact). If
+    /// the merge fails the existing fact is left in place
+    pub fn fact_merge(&mut self, var: Var, value: T) -> Result<(), T::Error>
+    where
+        T: Value,
+    {
+        let Some(existing) = self.known.get(&var) else {
+            // Can't fail, we just checked there was no existing fact
+            let _ = self.fact(var, value);
+            return Ok(());
+        };
+        let merged = T::merge(existing.clone(), value)?;
+        if let Some(previous) = self.known.insert(var, merged) {
+            self.record(Operation::Merge(var, previous));
+        }
+        Ok(())
+    }
 
-impl<T: Clone> Partial<T> {
-    fn try_resolve(
-        self,
-        known: &HashMap<Var, T>,
-    ) -> Result<TryResolveResult<T>, Error<T::Error>>
+    /// Record a fact for `var` after checking it against the value its
+    /// dependencies resolve to, e.g. to catch an inconsistent assertion as
+    /// soon as it is made
+    ///
+    /// Only `var` and the variables it depends on, directly or indirectly, are
+    /// resolved. If the resolved value doesn't [`merge`](Value::merge) with
+    /// `value` the merge error is returned as [`Error::Custom`], and any error
+    /// from resolution is returned as is. Either way the table is left
+    /// unchanged. A variable without dependencies is handled by
+    /// [`fact_merge`](Table::fact_merge)
+    pub fn fact_checked(
+        &mut self,
+        var: Var,
+        value: T,
+    ) -> Result<(), Error<T::Error>>
     where
         T: Value,
     {
-        let Self {
-            recursive,
-            result,
-            dependencies,
-        } = self;
-        let mut new_result = None;
-        let mut new_dependencies = HashSet::new();
-        for dep in dependencies {
-            // If we have a value for the variable we merge it into the result,
-            // otherwise it goes back in the dependency set
-            if let Some(known) = known.get(&dep) {
-                new_result = merge_opt(new_result, Some(known.clone()))?;
-            } else {
-                let _ = new_dependencies.insert(dep);
+        if !self.unknown.contains_key(&var) {
+            return self.fact_merge(var, value).map_err(Error::Custom);
+        }
+        let mut known = HashMap::new();
+        let mut unknown = HashMap::new();
+        let mut queue = vec![var];
+        while let Some(next) = queue.pop() {
+            if known.contains_key(&next) || unknown.contains_key(&next) {
+                continue;
             }
+            if let Some(value) = self.known.get(&next) {
+                let _ = known.insert(next, value.clone());
+            } else if let Some(dependencies) = self.unknown.get(&next) {
+                queue.extend(dependencies);
+                let _ = unknown.insert(next, dependencies.clone());
+            }
+        }
+        let mut resolution = Resolution::new(known, &unknown, &ByValue);
+        resolution.keep_only(var);
+        resolution
+            .run(usize::MAX)
+            .map_err(|e| e.labeled(&self.labels))?;
+        if let Some(resolved) = resolution.complete.remove(&var) {
+            let _ = T::merge(resolved, value.clone())?;
         }
+        // var has dependencies rather than a fact so this can't fail
+        let _ = self.fact(var, value);
+        Ok(())
+    }
 
-        // If new_result contains something then we learned something new from
-        // this pass
-        let progressed = new_result.is_some();
-        let result = merge_opt(result, new_result)?;
-
-        // If we still have dependencies to resolve the result is always
-        // Incomplete
-        if !new_dependencies.is_empty() {
-            return Ok(TryResolveResult::Incomplete(
-                Self {
-                    recursive,
-                    result,
-                    dependencies: new_dependencies,
-                },
-                progressed,
-            ));
+    /// Resolve the declared dependencies in the table
+    ///
+    /// Resolution is deterministic. It proceeds in passes over the unresolved
+    /// variables in ascending order of [`Var`], and the values of each
+    /// variable's dependencies are merged in ascending order too. This applies
+    /// to all of the `resolve` methods
+    pub fn resolve(self) -> Result<HashMap<Var, T>, Error<T::Error>>
+    where
+        T: Value,
+    {
+        self.resolve_bounded(usize::MAX)
+    }
+
+    /// Resolve the declared dependencies in the table, indexing the result by
+    /// each variable's number
+    ///
+    /// The vector has an entry for every variable produced by the table, `None`
+    /// for variables which were never given a fact or dependency. Variables the
+    /// table didn't produce, e.g. ones from another table, are left out
+    pub fn resolve_dense(self) -> Result<Vec<Option<T>>, Error<T::Error>>
+    where
+        T: Value,
+    {
+        let mut dense = Vec::new();
+        dense.resize_with(self.next_var, || None);
+        for (Var(var), value) in self.resolve()? {
+            if let Some(slot) = dense.get_mut(var) {
+                *slot = Some(value);
+            }
         }
+        Ok(dense)
+    }
 
-        // If our last remaining dependency is a recursive edge we can ask the
-        // type what the answer should be
-        if recursive {
-            return Ok(TryResolveResult::Complete(T::resolve_cycle(result)?));
+    /// Resolve the declared dependencies in the table, renumbering the
+    /// resolved variables to `Var(0)..Var(k)`
+    ///
+    /// Variables keep their relative order. The second map takes each
+    /// original variable to its new number
+    pub fn resolve_renumbered(self) -> Result<Renumbered<T>, Error<T::Error>>
+    where
+        T: Value,
+    {
+        let mut resolved = self.resolve()?.into_iter().collect::<Vec<_>>();
+        resolved.sort_unstable_by_key(|(Var(var), _)| *var);
+        let mut values = HashMap::with_capacity(resolved.len());
+        let mut mapping = HashMap::with_capacity(resolved.len());
+        for (new, (old, value)) in resolved.into_iter().enumerate() {
+            let _ = values.insert(Var(new), value);
+            let _ = mapping.insert(old, Var(new));
         }
+        Ok((values, mapping))
+    }
 
-        // Finally if we're not recursive and we don't have a partial result
-        // then we're stuck
-        let Some(result) = result else {
-            return Err(Error::NoProgress);
-        };
+    /// Resolve the declared dependencies in the table, also reporting the
+    /// cycles in the dependency graph
+    ///
+    /// Each cycle is a [component](Table::dependency_components) listed as a
+    /// walk through the dependencies, starting from its smallest variable.
+    /// Each variable depends on the next and the last depends on the first.
+    /// A variable can appear more than once if the component isn't a single
+    /// loop. Cycles are ordered by their first variable
+    pub fn resolve_with_cycles(self) -> Result<WithCycles<T>, Error<T::Error>>
+    where
+        T: Value,
+    {
+        let cycles = resolve::cycles(&self.unknown);
+        Ok((self.resolve()?, cycles))
+    }
 
-        Ok(TryResolveResult::Complete(result))
+    /// Resolve the declared dependencies in the table, also reporting which
+    /// variables were resolved by breaking a cycle
+    ///
+    /// The set holds every variable whose value was produced by
+    /// [`Value::resolve_cycle`], rather than only by merging the values of its
+    /// dependencies
+    pub fn resolve_with_provenance(
+        self,
+    ) -> Result<WithProvenance<T>, Error<T::Error>>
+    where
+        T: Value,
+    {
+        let mut resolution =
+            Resolution::new(self.known, &self.unknown, &ByValue);
+        resolution.record_cycles();
+        resolution
+            .run(usize::MAX)
+            .map_err(|e| e.labeled(&self.labels))?;
+        let cycles = resolution.take_cycles();
+        Ok((resolution.complete, cycles))
+    }
+
+    /// Make a single pass over the unresolved variables rather than resolving
+    /// the table completely
+    ///
+    /// Each variable is visited once in ascending order, so it can use the
+    /// value of any dependency resolved earlier in the same pass. The table
+    /// isn't consumed, facts can be added and the method called again to
+    /// resolve further. Like [`resolve_cloned`](Table::resolve_cloned) every
+    /// fact is cloned
+    #[must_use]
+    pub fn resolve_single_pass(&self) -> SinglePassResult<T>
+    where
+        T: Value,
+    {
+        let mut resolution =
+            Resolution::new(self.known.clone(), &self.unknown, &ByValue);
+        let mut errors = HashMap::new();
+        // The error handler never fails
+        let _ = resolution.pass(&mut |var, _, e| {
+            let _ = errors.insert(var, e.into_inner());
+            Ok(())
+        });
+        SinglePassResult {
+            residual: resolution.take_remaining(),
+            resolved: resolution.complete,
+            errors,
+        }
+    }
+
+    /// Resolve the declared dependencies without consuming the table, so more
+    /// facts and dependencies can be added and the table resolved again
+    ///
+    /// Otherwise the same as [`resolve`](Table::resolve), except that every
+    /// fact is cloned up front where `resolve` moves them into the result
+    pub fn resolve_cloned(&self) -> Result<HashMap<Var, T>, Error<T::Error>>
+    where
+        T: Value,
+    {
+        let mut resolution =
+            Resolution::new(self.known.clone(), &self.unknown, &ByValue);
+        resolution
+            .run(usize::MAX)
+            .map_err(|e| e.labeled(&self.labels))?;
+        Ok(resolution.complete)
+    }
+
+    /// Resolve the declared dependencies in the table, failing with
+    /// [`Error::IterationLimit`] if the table isn't resolved after
+    /// `max_passes` passes over the unresolved variables
+    pub fn resolve_bounded(
+        self,
+        max_passes: usize,
+    ) -> Result<HashMap<Var, T>, Error<T::Error>>
+    where
+        T: Value,
+    {
+        self.resolve_with(&ByValue, max_passes)
+    }
+
+    /// Resolve a single variable, stopping as soon as its value is known
+    ///
+    /// Other variables are still resolved along the way but no more passes
+    /// are made once `target` is complete. Fails with [`Error::NoProgress`] if
+    /// `target` can't be resolved, including if it isn't part of the table
+    ///
+    /// Only `target` is returned, so the value of a variable is moved into the
+    /// last variable depending on it rather than cloned. Values are only
+    /// cloned for variables with more than one dependent
+    pub fn resolve_var(self, target: Var) -> Result<T, Error<T::Error>>
+    where
+        T: Value,
+    {
+        let mut resolution =
+            Resolution::new(self.known, &self.unknown, &ByValue);
+        resolution.keep_only(target);
+        resolution
+            .run_until(usize::MAX, |complete| complete.contains_key(&target))
+            .map_err(|e| e.labeled(&self.labels))?;
+        resolution.complete.remove(&target).ok_or_else(|| {
+            Error::NoProgress {
+                stuck: HashMap::from([(target, HashSet::new())]),
+                labels: HashMap::new(),
+            }
+            .labeled(&self.labels)
+        })
+    }
+
+    /// Resolve the declared dependencies in the table using a condensation
+    /// computed earlier by [`Table::condense`]
+    ///
+    /// The condensation is trusted, the results are unspecified if
+    /// dependencies have changed since it was computed
+    ///
+    /// # Panics
+    /// In debug builds, if the condensation doesn't match the table's
+    /// dependencies
+    pub fn resolve_from_condensation(
+        self,
+        condensation: &CondensedTable<T>,
+    ) -> Result<HashMap<Var, T>, Error<T::Error>>
+    where
+        T: Value,
+    {
+        debug_assert!(
+            same_components(
+                &condensation.components,
+                &resolve::condense(&self.unknown)
+            ),
+            "Condensation doesn't match the table's dependencies"
+        );
+        let mut resolution = Resolution::with_components(
+            self.known,
+            &self.unknown,
+            condensation.components.clone(),
+            &ByValue,
+        );
+        resolution
+            .run(usize::MAX)
+            .map_err(|e| e.labeled(&self.labels))?;
+        Ok(resolution.complete)
+    }
+
+    /// Resolve the declared dependencies in the table, yielding each variable
+    /// as soon as it is resolved
+    ///
+    /// Facts are yielded first, then each variable in the order it was
+    /// resolved. If resolution fails the error is the last item
+    pub fn resolve_stream(
+        self,
+    ) -> impl Iterator<Item = Result<(Var, T), Error<T::Error>>>
+    where
+        T: Value,
+    {
+        let labels = self.labels;
+        Stream::new(self.known, &self.unknown)
+            .map(move |item| item.map_err(|e| e.labeled(&labels)))
+    }
+
+    /// Resolve the declared dependencies in the table, asking `oracle` for the
+    /// value of any variable which has neither a fact nor dependencies of its
+    /// own
+    ///
+    /// Facts always take priority over the oracle. The oracle is called at
+    /// most once per variable and only for variables something depends on, if
+    /// it returns `None` the variable is resolved with
+    /// [`Value::resolve_cycle`] as usual
+    pub fn resolve_hybrid(
+        self,
+        oracle: impl FnMut(Var) -> Option<T>,
+    ) -> Result<HashMap<Var, T>, Error<T::Error>>
+    where
+        T: Value,
+    {
+        let mut resolution =
+            Resolution::new(self.known, &self.unknown, &ByValue);
+        resolution.consult(&self.unknown, oracle);
+        resolution
+            .run(usize::MAX)
+            .map_err(|e| e.labeled(&self.labels))?;
+        Ok(resolution.complete)
+    }
+
+    /// Resolve the declared dependencies in the table, keeping whatever was
+    /// resolved before an error
+    ///
+    /// Returns every variable which was resolved along with the error which
+    /// stopped resolution, if any. If resolution succeeds the map is the same
+    /// as the one returned by [`resolve`](Table::resolve)
+    #[must_use]
+    pub fn resolve_partial(self) -> (HashMap<Var, T>, Option<Error<T::Error>>)
+    where
+        T: Value,
+    {
+        let mut resolution =
+            Resolution::new(self.known, &self.unknown, &ByValue);
+        let error = resolution
+            .run(usize::MAX)
+            .err()
+            .map(|e| e.labeled(&self.labels));
+        (resolution.complete, error)
     }
-}
 
-fn merge_opt<T: Value>(
-    left: Option<T>,
-    right: Option<T>,
-) -> Result<Option<T>, T::Error> {
-    match (left, right) {
-        (None, None) => Ok(None),
-        (Some(left), None) => Ok(Some(left)),
-        (None, Some(right)) => Ok(Some(right)),
-        (Some(left), Some(right)) => Ok(Some(T::merge(left, right)?)),
+    /// Resolve the declared dependencies in the table by picking between
+    /// values rather than merging them
+    ///
+    /// Whenever two dependencies contribute to a row the greater of the two
+    /// according to `pick` is kept, so passing [`Ord::cmp`] resolves each row
+    /// to the maximum of its dependencies. A cycle resolves to the best value
+    /// seen from outside of the cycle, if there is no such value resolution
+    /// fails with [`Error::NoProgress`]
+    pub fn resolve_by(
+        self,
+        pick: impl Fn(&T, &T) -> Ordering,
+    ) -> Result<HashMap<Var, T>, Error<Infallible>> {
+        self.resolve_with(&ByOrdering(pick), usize::MAX)
+    }
+
+    /// Resolve the declared dependencies in the table, continuing past any
+    /// errors
+    ///
+    /// Variables which fail to resolve, either because [`Value`] raised an
+    /// error or because resolution stalled, are returned grouped into their
+    /// dependency cycles instead. Any variables depending on them also fail to
+    /// resolve
+    #[must_use]
+    pub fn resolve_lenient(self) -> (HashMap<Var, T>, Vec<Unresolved<T::Error>>)
+    where
+        T: Value,
+    {
+        let mut resolution =
+            Resolution::new(self.known, &self.unknown, &ByValue);
+        let unresolved = resolution
+            .run_lenient()
+            .into_iter()
+            .map(|(vars, error)| {
+                let cycle = vars.len() > 1
+                    || vars.iter().any(|var| {
+                        self.unknown
+                            .get(var)
+                            .is_some_and(|deps| deps.contains(var))
+                    });
+                Unresolved { vars, cycle, error }
+            })
+            .collect();
+        (resolution.complete, unresolved)
+    }
+
+    /// Resolve the declared dependencies in the table, resolving groups of
+    /// variables which don't depend on each other on separate threads
+    ///
+    /// The values are the same as [`resolve`](Table::resolve). If more than
+    /// one group fails the error comes from the group containing the smallest
+    /// variable, and only mentions the variables in that group
+    #[cfg(feature = "rayon")]
+    pub fn resolve_parallel(self) -> Result<HashMap<Var, T>, Error<T::Error>>
+    where
+        T: Value + Send + Sync,
+        T::Error: Send,
+    {
+        use rayon::prelude::*;
+
+        fn take<V>(
+            group: &HashSet<Var>,
+            table: &mut HashMap<Var, V>,
+        ) -> HashMap<Var, V> {
+            group
+                .iter()
+                .filter_map(|var| Some((*var, table.remove(var)?)))
+                .collect()
+        }
+
+        let mut known = self.known;
+        let mut unknown = self.unknown;
+        // Split the table up front so each group owns its facts
+        let groups = resolve::independent(&unknown)
+            .into_iter()
+            .map(|group| (take(&group, &mut known), take(&group, &mut unknown)))
+            .collect::<Vec<_>>();
+        let results = groups
+            .into_par_iter()
+            .map(|(known, unknown)| {
+                let mut resolution = Resolution::new(known, &unknown, &ByValue);
+                resolution.run(usize::MAX).map(|()| resolution.complete)
+            })
+            .collect::<Vec<_>>();
+        // Facts nothing depends on aren't in any group
+        for result in results {
+            known.extend(result.map_err(|e| e.labeled(&self.labels))?);
+        }
+        Ok(known)
+    }
+
+    fn resolve_with<S: Strategy<T>>(
+        self,
+        strategy: &S,
+        max_passes: usize,
+    ) -> Result<HashMap<Var, T>, Error<S::Error>> {
+        let mut resolution =
+            Resolution::new(self.known, &self.unknown, strategy);
+        resolution
+            .run(max_passes)
+            .map_err(|e| e.labeled(&self.labels))?;
+        Ok(resolution.complete)
     }
 }
@@ -0,0 +1,176 @@
+//! Re-resolution of a table after it changes, see [`Table::into_resolved`]
+
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    Error, Table, Value, Var,
+    resolve::{self, ByValue, Resolution},
+};
+
+/// A [`Table`] together with its resolved values, which can be edited and
+/// resolved again without starting from scratch
+///
+/// Each edit marks the variable it touches as invalid. [`re_resolve`]
+/// recomputes the invalid variables and everything which depends on them,
+/// directly or indirectly, and reuses the values of everything else
+///
+/// [`re_resolve`]: Resolved::re_resolve
+#[derive(Debug)]
+pub struct Resolved<T> {
+    table: Table<T>,
+    values: HashMap<Var, T>,
+    // Invalidated since the last successful call to re_resolve
+    dirty: HashSet<Var>,
+}
+
+impl<T> Table<T> {
+    /// Prepare the table for incremental resolution
+    ///
+    /// Nothing is resolved until the first call to
+    /// [`re_resolve`](Resolved::re_resolve), which resolves the whole table
+    #[must_use]
+    pub fn into_resolved(self) -> Resolved<T> {
+        let dirty = self
+            .known
+            .keys()
+            .chain(self.unknown.keys())
+            .copied()
+            .collect();
+        Resolved {
+            table: self,
+            values: HashMap::new(),
+            dirty,
+        }
+    }
+}
+
+impl<T> Resolved<T> {
+    /// The values as of the last call to [`re_resolve`](Resolved::re_resolve)
+    ///
+    /// Variables which failed to resolve have no value
+    #[must_use]
+    pub fn values(&self) -> &HashMap<Var, T> {
+        &self.values
+    }
+
+    /// The underlying table
+    #[must_use]
+    pub fn table(&self) -> &Table<T> {
+        &self.table
+    }
+
+    /// Create a new variable, see [`Table::var`]
+    pub fn var(&mut self) -> Var {
+        self.table.var()
+    }
+
+    /// Record a fact for `var`, replacing any existing fact, see
+    /// [`Table::fact`]
+    ///
+    /// Returns the fact which was replaced, if any
+    pub fn fact(&mut self, var: Var, value: T) -> Option<T> {
+        let previous = self.table.retract(var);
+        let Ok(()) = self.table.fact(var, value) else {
+            unreachable!("The existing fact was just retracted")
+        };
+        self.invalidate(var);
+        previous
+    }
+
+    /// Remove the fact for `var`, see [`Table::retract`]
+    pub fn retract(&mut self, var: Var) -> Option<T> {
+        self.invalidate(var);
+        self.table.retract(var)
+    }
+
+    /// Add a dependency, see [`Table::dependency`]
+    ///
+    /// # Panics
+    /// As for [`Table::dependency`]
+    pub fn dependency(&mut self, var: Var, depends_on: Var) {
+        self.table.dependency(var, depends_on);
+        self.invalidate(var);
+    }
+
+    /// Remove a dependency, see [`Table::remove_dependency`]
+    pub fn remove_dependency(&mut self, var: Var, depends_on: Var) -> bool {
+        self.invalidate(var);
+        self.table.remove_dependency(var, depends_on)
+    }
+
+    /// Mark `var` as needing to be recomputed by the next call to
+    /// [`re_resolve`](Resolved::re_resolve)
+    ///
+    /// The methods which edit the table call this themselves, it only needs to
+    /// be called directly if the way `var` resolves has changed some other way
+    pub fn invalidate(&mut self, var: Var) {
+        let _ = self.dirty.insert(var);
+    }
+
+    // The invalid variables and everything which depends on them
+    fn affected(&self) -> HashSet<Var> {
+        let dependents =
+            resolve::dependency_graph(&self.table.unknown).reverse();
+        let mut affected = self.dirty.clone();
+        let mut queue = self.dirty.iter().copied().collect::<Vec<_>>();
+        while let Some(var) = queue.pop() {
+            for dependent in dependents.children(var).into_iter().flatten() {
+                if affected.insert(dependent) {
+                    queue.push(dependent);
+                }
+            }
+        }
+        affected
+    }
+}
+
+impl<T: Clone + Value> Resolved<T> {
+    /// Recompute every variable invalidated since the last successful call,
+    /// and everything which depends on them
+    ///
+    /// Only the affected part of the dependency graph is condensed and
+    /// resolved, values of the variables it depends on are reused. The values
+    /// are the same as resolving the whole table with [`Table::resolve`]. If
+    /// resolution fails the variables resolved before the error keep their
+    /// values, as with [`Table::resolve_partial`], and the rest are recomputed
+    /// by the next call
+    pub fn re_resolve(&mut self) -> Result<(), Error<T::Error>> {
+        if self.dirty.is_empty() {
+            return Ok(());
+        }
+        let mut affected = self.affected();
+        let mut known = HashMap::new();
+        let mut unknown = HashMap::new();
+        for &var in &affected {
+            let _ = self.values.remove(&var);
+            if let Some(value) = self.table.known.get(&var) {
+                let _ = known.insert(var, value.clone());
+            } else if let Some(dependencies) = self.table.unknown.get(&var) {
+                let _ = unknown.insert(var, dependencies.clone());
+            }
+        }
+        // Anything else the affected variables depend on keeps its value
+        for dependency in unknown.values().flatten() {
+            if affected.contains(dependency) {
+                continue;
+            }
+            if let Some(value) = self.values.get(dependency) {
+                let _ = known.insert(*dependency, value.clone());
+            }
+        }
+
+        let mut resolution = Resolution::new(known, &unknown, &ByValue);
+        let result = resolution
+            .run(usize::MAX)
+            .map_err(|e| e.labeled(&self.table.labels));
+        // Keep whatever was resolved before any error, the rest is retried by
+        // the next call
+        for (var, value) in resolution.complete {
+            if affected.remove(&var) {
+                let _ = self.values.insert(var, value);
+            }
+        }
+        self.dirty = affected;
+        result
+    }
+}
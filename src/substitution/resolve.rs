@@ -0,0 +1,901 @@
+//! Fixpoint resolution of a substitution table
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet, VecDeque},
+    convert::Infallible,
+    mem,
+};
+
+use super::{Error, MergeContext, Value, Var};
+use crate::graph::Graph;
+
+/// In progress resolution of a table
+///
+/// Variables move from the partials table into the complete table as their
+/// dependencies are resolved, one [pass](Resolution::pass) at a time
+pub(super) struct Resolution<'a, T, S> {
+    strategy: &'a S,
+    // This is the table of resolved information, the goal is to move all of
+    // the variables into this table
+    pub(super) complete: HashMap<Var, T>,
+    // Partials holds the partial inference results
+    partials: HashMap<Var, Partial<T>>,
+    // Variables added to complete by pass, only recorded if enabled by
+    // Resolution::record_learned
+    learned: Option<Vec<Var>>,
+    // Variables whose value came from Strategy::resolve_cycle, only recorded
+    // if enabled by Resolution::record_cycles
+    cycles: Option<HashSet<Var>>,
+    // Only set by Resolution::keep_only, see Consumers
+    consumers: Option<Consumers>,
+}
+
+// The values a resolution still has to hand out when only one variable is
+// wanted at the end. Each value is moved into its last dependent rather than
+// cloned
+struct Consumers {
+    // Left in the complete table however many dependents it has
+    keep: Var,
+    // Number of unresolved variables still waiting on each variable
+    remaining: HashMap<Var, usize>,
+}
+
+impl Consumers {
+    // The value of `dep` for one of its dependents
+    fn take<T: Clone>(
+        &mut self,
+        complete: &mut HashMap<Var, T>,
+        dep: Var,
+    ) -> Option<T> {
+        // Unresolved dependencies are asked for again on the next pass
+        if dep != self.keep
+            && complete.contains_key(&dep)
+            && let Some(remaining) = self.remaining.get_mut(&dep)
+        {
+            *remaining -= 1;
+            if *remaining == 0 {
+                return complete.remove(&dep);
+            }
+        }
+        complete.get(&dep).cloned()
+    }
+}
+
+impl<'a, T: Clone, S: Strategy<T>> Resolution<'a, T, S> {
+    pub(super) fn new(
+        known: HashMap<Var, T>,
+        unknown: &HashMap<Var, HashSet<Var>>,
+        strategy: &'a S,
+    ) -> Self {
+        let components = condense(unknown);
+        Self::with_components(known, unknown, components, strategy)
+    }
+
+    /// Like [`new`](Resolution::new) but with the strongly connected
+    /// components of the dependency graph already computed by [`condense`]
+    pub(super) fn with_components(
+        known: HashMap<Var, T>,
+        unknown: &HashMap<Var, HashSet<Var>>,
+        components: Vec<HashSet<Var>>,
+        strategy: &'a S,
+    ) -> Self {
+        Self {
+            strategy,
+            // We start by populating the complete table with our initial set of
+            // facts
+            complete: known,
+            partials: prepare_partials(unknown, components),
+            learned: None,
+            cycles: None,
+            consumers: None,
+        }
+    }
+
+    /// Only keep the value of `keep` in [`complete`](Resolution::complete)
+    ///
+    /// Any other value is moved into the last variable which depends on it,
+    /// rather than cloned, so it is gone from the complete table by the time
+    /// resolution finishes
+    pub(super) fn keep_only(&mut self, keep: Var) {
+        // Facts don't need resolving and once their value has been moved out
+        // of the complete table they would look like stuck variables
+        let complete = &self.complete;
+        self.partials.retain(|var, _| !complete.contains_key(var));
+        let mut remaining = HashMap::new();
+        for partial in self.partials.values() {
+            for &dep in &partial.dependencies {
+                *remaining.entry(dep).or_default() += 1;
+            }
+        }
+        self.consumers = Some(Consumers { keep, remaining });
+    }
+
+    /// Ask `oracle` for the value of each variable with no dependencies in
+    /// `unknown` which isn't already known, in ascending order
+    ///
+    /// Otherwise these variables would only be resolved by
+    /// [`Value::resolve_cycle`], so the oracle is asked at most once for each,
+    /// any answers are recorded in [`complete`](Resolution::complete)
+    pub(super) fn consult(
+        &mut self,
+        unknown: &HashMap<Var, HashSet<Var>>,
+        mut oracle: impl FnMut(Var) -> Option<T>,
+    ) {
+        let mut leaves = self
+            .partials
+            .keys()
+            .filter(|var| {
+                !unknown.contains_key(var) && !self.complete.contains_key(var)
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        leaves.sort_unstable_by_key(|Var(var)| *var);
+        for var in leaves {
+            if let Some(value) = oracle(var) {
+                let _ = self.partials.remove(&var);
+                let _ = self.complete.insert(var, value);
+            }
+        }
+    }
+
+    /// Start recording the variables resolved by each pass, see
+    /// [`take_learned`](Resolution::take_learned)
+    pub(super) fn record_learned(&mut self) {
+        self.learned = Some(Vec::new());
+    }
+
+    /// The variables resolved since the last call, in the order they were
+    /// resolved
+    pub(super) fn take_learned(&mut self) -> Vec<Var> {
+        self.learned.as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    /// Start recording the variables resolved by breaking a cycle, see
+    /// [`take_cycles`](Resolution::take_cycles)
+    pub(super) fn record_cycles(&mut self) {
+        self.cycles = Some(HashSet::new());
+    }
+
+    /// The variables whose value was produced by
+    /// [`Strategy::resolve_cycle`] so far
+    pub(super) fn take_cycles(&mut self) -> HashSet<Var> {
+        self.cycles.take().unwrap_or_default()
+    }
+
+    /// True if there are no unresolved variables left
+    pub(super) fn is_done(&self) -> bool {
+        self.partials.is_empty()
+    }
+
+    /// Resolve as much of the table as possible, failing with
+    /// [`Error::NoProgress`] if resolution gets stuck or
+    /// [`Error::IterationLimit`] if it takes more than `max_passes` passes
+    ///
+    /// On failure the variables resolved so far are left in
+    /// [`complete`](Resolution::complete)
+    pub(super) fn run(
+        &mut self,
+        max_passes: usize,
+    ) -> Result<(), Error<S::Error>> {
+        self.run_until(max_passes, |_| false)
+    }
+
+    /// Like [`run`](Resolution::run) but stops early once `done` returns true
+    /// for the variables resolved so far
+    pub(super) fn run_until(
+        &mut self,
+        max_passes: usize,
+        done: impl Fn(&HashMap<Var, T>) -> bool,
+    ) -> Result<(), Error<S::Error>> {
+        let mut passes = 0;
+        // Loop until we run out of partials
+        while !self.partials.is_empty() && !done(&self.complete) {
+            if passes == max_passes {
+                return Err(Error::IterationLimit { passes });
+            }
+            passes += 1;
+            // If we made no progress, bail
+            if !self.pass(&mut |_, _, e| Err(e))? {
+                return Err(self.stuck());
+            }
+        }
+        Ok(())
+    }
+
+    /// Give up on the remaining variables, reporting what each was still
+    /// waiting on
+    pub(super) fn stuck(&mut self) -> Error<S::Error> {
+        Error::NoProgress {
+            stuck: self.take_remaining(),
+            labels: HashMap::new(),
+        }
+    }
+
+    /// Remove the unresolved variables, returning what each was still waiting
+    /// on
+    pub(super) fn take_remaining(&mut self) -> HashMap<Var, HashSet<Var>> {
+        mem::take(&mut self.partials)
+            .into_iter()
+            .map(|(var, partial)| (var, partial.dependencies))
+            .collect()
+    }
+
+    /// Resolve as much of the table as possible, continuing past errors
+    ///
+    /// Returns the variables which could not be resolved grouped by the
+    /// strongly connected component they belong to, along with the first error
+    /// raised while resolving each group, if any
+    pub(super) fn run_lenient(
+        &mut self,
+    ) -> Vec<(HashSet<Var>, Option<S::Error>)> {
+        let mut failed =
+            HashMap::<usize, (HashSet<Var>, Option<S::Error>)>::new();
+        while !self.partials.is_empty() {
+            let progress = self.pass(&mut |var, component, e| {
+                let (vars, error) = failed.entry(component).or_default();
+                let _ = vars.insert(var);
+                let _ = error.get_or_insert(e.into_inner());
+                Ok(())
+            });
+            // The error handler never fails
+            if !matches!(progress, Ok(true)) {
+                break;
+            }
+        }
+        for (var, partial) in mem::take(&mut self.partials) {
+            let _ = failed.entry(partial.component).or_default().0.insert(var);
+        }
+        failed.into_values().collect()
+    }
+
+    /// Check each currently unresolved variable once, returns true if anything
+    /// was learned
+    ///
+    /// `on_error` is called with the variable and component which raised an
+    /// error. If it returns Ok the variable is dropped from the resolution and
+    /// the pass continues
+    pub(super) fn pass(
+        &mut self,
+        on_error: &mut impl FnMut(
+            Var,
+            usize,
+            Failure<S::Error>,
+        ) -> Result<(), Failure<S::Error>>,
+    ) -> Result<bool, Failure<S::Error>> {
+        let mut progress = false;
+        // For unresolved partials in the loop below
+        let capacity = self.partials.len();
+        let mut partials =
+            mem::replace(&mut self.partials, HashMap::with_capacity(capacity))
+                .into_iter()
+                .collect::<Vec<_>>();
+        // Visit variables in ascending order so values are merged in the same
+        // order on every run
+        partials.sort_unstable_by_key(|(Var(var), _)| *var);
+
+        for (var, partial) in partials {
+            if self.complete.contains_key(&var) {
+                continue;
+            }
+            let component = partial.component;
+            // Attempt to progress the partial result with respect to what we
+            // know so far
+            let complete = &mut self.complete;
+            let consumers = &mut self.consumers;
+            let fetch = |dep| match consumers {
+                Some(consumers) => consumers.take(complete, dep),
+                None => complete.get(&dep).cloned(),
+            };
+            match partial.try_resolve(fetch, self.strategy) {
+                Ok(TryResolveResult::Complete(result, cycle)) => {
+                    // If we resolved all of our dependencies record the result
+                    // in the completed table and mark that we made progress
+                    let _ = self.complete.insert(var, result);
+                    if let Some(learned) = &mut self.learned {
+                        learned.push(var);
+                    }
+                    if let Some(cycles) = &mut self.cycles
+                        && cycle
+                    {
+                        let _ = cycles.insert(var);
+                    }
+                    progress = true;
+                }
+                Ok(TryResolveResult::Incomplete(partial, progressed)) => {
+                    // If we still have outstanding dependencies we store the
+                    // new partial for the next pass. In this case try_resolve
+                    // also tells us if we managed to learn anything new this
+                    // pass so record that too
+                    let _ = self.partials.insert(var, partial);
+                    progress = progress || progressed;
+                }
+                Err(e) => {
+                    // Dropping the variable changes what later passes will see
+                    // so it counts as progress
+                    on_error(var, component, e)?;
+                    progress = true;
+                }
+            }
+        }
+
+        Ok(progress)
+    }
+}
+
+/// Resolves a table one pass at a time, yielding variables as they are
+/// resolved
+pub(super) struct Stream<T: Value> {
+    resolution: Resolution<'static, T, ByValue>,
+    // Resolved but not yielded yet
+    ready: VecDeque<Var>,
+    // Yielded once everything in ready has been
+    end: Option<Error<T::Error>>,
+    finished: bool,
+}
+
+impl<T: Value + Clone> Stream<T> {
+    pub(super) fn new(
+        known: HashMap<Var, T>,
+        unknown: &HashMap<Var, HashSet<Var>>,
+    ) -> Self {
+        let mut resolution = Resolution::new(known, unknown, &ByValue);
+        resolution.record_learned();
+        // Facts are resolved from the start
+        let mut ready = resolution.complete.keys().copied().collect::<Vec<_>>();
+        ready.sort_unstable_by_key(|Var(var)| *var);
+        Self {
+            resolution,
+            ready: ready.into(),
+            end: None,
+            finished: false,
+        }
+    }
+}
+
+impl<T: Value + Clone> Iterator for Stream<T> {
+    type Item = Result<(Var, T), Error<T::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(var) = self.ready.pop_front() {
+                let value = self.resolution.complete[&var].clone();
+                return Some(Ok((var, value)));
+            }
+            if let Some(error) = self.end.take() {
+                self.finished = true;
+                return Some(Err(error));
+            }
+            if self.finished || self.resolution.is_done() {
+                return None;
+            }
+            match self.resolution.pass(&mut |_, _, e| Err(e)) {
+                Ok(true) => (),
+                Ok(false) => self.end = Some(self.resolution.stuck()),
+                Err(e) => self.end = Some(e.into()),
+            }
+            // Anything resolved before an error is still yielded first
+            self.ready.extend(self.resolution.take_learned());
+        }
+    }
+}
+
+/// Prepare the partials table for a caller resolving the table themselves
+pub(super) fn partial_views<T>(
+    known: &HashMap<Var, T>,
+    unknown: &HashMap<Var, HashSet<Var>>,
+) -> HashMap<Var, PartialView<T>> {
+    // Facts show up in the dependency graph too, they're skipped by the
+    // resolve loop so they shouldn't be handed out here
+    prepare_partials(unknown, condense(unknown))
+        .into_iter()
+        .filter(|(var, _)| !known.contains_key(var))
+        .map(|(var, partial)| (var, PartialView(partial)))
+        .collect()
+}
+
+/// Compute the strongly connected components of the dependency graph
+pub(super) fn condense(
+    unknown: &HashMap<Var, HashSet<Var>>,
+) -> Vec<HashSet<Var>> {
+    dependency_graph(unknown)
+        .strongly_connected_components()
+        .collect()
+}
+
+/// Split the dependency graph into groups of variables which don't depend on
+/// each other in either direction, so each group can be resolved on its own.
+/// Groups are ordered by their smallest variable
+#[cfg(feature = "rayon")]
+pub(super) fn independent(
+    unknown: &HashMap<Var, HashSet<Var>>,
+) -> Vec<HashSet<Var>> {
+    // Following edges both ways the strongly connected components are the
+    // connected components
+    let mut graph = dependency_graph(unknown);
+    for (node, dependents) in graph.reverse() {
+        graph.add_edges(node, &dependents);
+    }
+    let mut groups = graph.strongly_connected_components().collect::<Vec<_>>();
+    groups
+        .sort_unstable_by_key(|group| group.iter().map(|Var(var)| *var).min());
+    groups
+}
+
+// Each component containing a cycle as a closed walk starting from its smallest
+// variable, each variable depends on the next and the last on the first.
+// Components are ordered by their smallest variable
+pub(super) fn cycles(unknown: &HashMap<Var, HashSet<Var>>) -> Vec<Vec<Var>> {
+    let mut cycles = condense(unknown)
+        .into_iter()
+        .filter(|component| {
+            component.len() > 1
+                || component.iter().all(|var| {
+                    unknown.get(var).is_some_and(|d| d.contains(var))
+                })
+        })
+        .map(|component| closed_walk(unknown, &component))
+        .collect::<Vec<_>>();
+    cycles.sort_unstable_by_key(|walk| walk[0].0);
+    cycles
+}
+
+// Visit every variable in the component in ascending order, taking the
+// shortest route each time, then return to the start
+fn closed_walk(
+    unknown: &HashMap<Var, HashSet<Var>>,
+    component: &HashSet<Var>,
+) -> Vec<Var> {
+    let mut targets = component.iter().copied().collect::<Vec<_>>();
+    targets.sort_unstable_by_key(|Var(var)| *var);
+    let start = targets[0];
+    let mut walk = vec![start];
+    let mut current = start;
+    for target in targets {
+        if !walk.contains(&target) {
+            walk.extend(shortest_path(unknown, component, current, target));
+            current = target;
+        }
+    }
+    // Drop the final step, the walk wraps around to the start
+    let mut back = shortest_path(unknown, component, current, start);
+    let _ = back.pop();
+    walk.extend(back);
+    walk
+}
+
+// The steps from `from` to `to` without leaving the component, excluding
+// `from`. If they're the same this is the shortest cycle through `from`
+fn shortest_path(
+    unknown: &HashMap<Var, HashSet<Var>>,
+    component: &HashSet<Var>,
+    from: Var,
+    to: Var,
+) -> Vec<Var> {
+    let neighbours = |var: Var| {
+        let mut neighbours = unknown
+            .get(&var)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|dep| component.contains(dep))
+            .collect::<Vec<_>>();
+        neighbours.sort_unstable_by_key(|Var(var)| *var);
+        neighbours
+    };
+    let mut parents = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(var) = queue.pop_front() {
+        for next in neighbours(var) {
+            if parents.contains_key(&next) {
+                continue;
+            }
+            let _ = parents.insert(next, var);
+            if next == to {
+                let mut path = vec![to];
+                let mut step = var;
+                while step != from {
+                    path.push(step);
+                    step = parents[&step];
+                }
+                path.reverse();
+                return path;
+            }
+            queue.push_back(next);
+        }
+    }
+    unreachable!("Components are strongly connected")
+}
+
+pub(super) fn dependency_graph(
+    unknown: &HashMap<Var, HashSet<Var>>,
+) -> Graph<Var> {
+    let mut graph = Graph::new();
+    for (&src, dsts) in unknown {
+        graph.add_edges(src, dsts);
+    }
+    graph
+}
+
+// The major point of this and the reason we can't just use the original
+// unknown table directly for resolution has to do with cycles in the
+// dependency graph.
+//
+// We start by finding all of the strongly connected components in the
+// dependency graph, this is a slightly wider condition than true cycles
+//
+// Consider a single strongly connected component with no incomming or
+// outgoing edges. By the definition of a strongly connected component every
+// node in the component is reachable by some path from every other node.
+// Since our edges are dependencies that means every node in the component
+// is (transitivley) dependent on every other node in the component and
+// ultimatly on itself. As there are no other outgoing nodes there is no
+// extra information to be had and every node in the component must resolve
+// to the same value in the event we can decide what value that should be.
+// So we can replace the entire component with a single 'virtual' node with
+// one dependency edge pointing back at itself, this is a situation we know
+// how to resolve.
+//
+// Now consider a component with some number of outgoing edges (e.g some
+// nodes inside the component depend on some nodes outside of the
+// component). Because any node inside the component reachable from any
+// other node then there is a path from every node inside the component to
+// every non-component node depended on by any node in the component. So, to
+// labor the point, every node in the component (transitivley) depends on
+// every non-component node depended on by any component node. Similar to
+// the first case we can achive the same affect by collapsing the entire
+// component into a single virtual node with a recursive dependency & a
+// dependency on each of the non-component nodes dependended on by any node
+// in the component.
+//
+// Finally consider incoming dependency edges. After collapsing the
+// component into a single node we could go through the entire graph and
+// patch up any dependency edge targeting any component node to refer
+// instead to the virtual node. This is awkward though since we index edges
+// by source node so we have to traverse every other edge in the graph in
+// order to find edges which travel into the component. Since the premise of
+// the virtual node transformation is that every node in the component is
+// essentially identical we can instead make very component node look like
+// the proposed virtual node (e.g with a direct dependency for each edge
+// leaving the component and one recursive dependency edge). This has the
+// same affect as the virtual node approach but means we don't need to patch
+// up incoming edges or translate the virtual node(s) back to the original
+// nodes after inference
+fn prepare_partials<T>(
+    unknown: &HashMap<Var, HashSet<Var>>,
+    sccs: Vec<HashSet<Var>>,
+) -> HashMap<Var, Partial<T>> {
+    let mut graph = dependency_graph(unknown);
+
+    // For each of the strongly connected components
+    let mut components = HashMap::new();
+    for (id, component) in sccs.into_iter().enumerate() {
+        // Compute the set of dependencies of the component, this is the union
+        // of all of the dependencies of all of the nodes in the component
+        // minus any nodes which are themselves members of the component
+        let all_dependencies = component
+            .iter()
+            .filter_map(|&node| graph.children(node))
+            .flatten()
+            .filter(|node| !component.contains(node))
+            .collect();
+        // For each node in the component we delete all of the original edges
+        // it had and add one for each of the components dependencies and one
+        // recursive edge
+        for &node in &component {
+            graph.delete_outgoing_edges(node);
+            graph.add_edges(node, &all_dependencies);
+            graph.add_edge(node, node);
+            let _ = components.insert(node, (id, component.len()));
+        }
+    }
+
+    // Now we can build our partials table
+    let mut result = HashMap::new();
+    for (var, mut dependencies) in graph {
+        let recursive = dependencies.remove(&var);
+        let (component, cycle_size) = components[&var];
+        let _ = result.insert(
+            var,
+            Partial {
+                var,
+                recursive,
+                component,
+                cycle_size,
+                result: None,
+                dependencies,
+            },
+        );
+    }
+
+    result
+}
+
+/// Partial result during inference
+struct Partial<T> {
+    // The variable being resolved
+    var: Var,
+    // True if the variable assigned to this partial depends on itself
+    recursive: bool,
+    // Identifies the strongly connected component the variable belongs to
+    component: usize,
+    // Number of variables in the component
+    cycle_size: usize,
+    // Partial result, if known, along with the first dependency which
+    // contributed to it
+    result: Option<(Var, T)>,
+    // Remaining dependencies, if any
+    dependencies: HashSet<Var>,
+}
+
+/// Read only view of a variable which hasn't been resolved yet, see
+/// [`Table::into_partials`](super::Table::into_partials)
+#[expect(missing_debug_implementations)]
+pub struct PartialView<T>(Partial<T>);
+
+/// Outcome of [`PartialView::try_step`]
+#[derive(Debug)]
+pub enum Step<T> {
+    /// Every dependency has been resolved, this is the final value of the
+    /// variable
+    Complete(T),
+    /// There are still outstanding dependencies. True if anything new was
+    /// learned in this step
+    Incomplete(bool),
+}
+
+impl<T> PartialView<T> {
+    /// True if the variable depends on itself, possibly via a cycle of other
+    /// variables
+    #[must_use]
+    pub fn recursive(&self) -> bool {
+        self.0.recursive
+    }
+
+    /// The dependencies which haven't been resolved yet
+    #[must_use]
+    pub fn dependencies(&self) -> &HashSet<Var> {
+        &self.0.dependencies
+    }
+}
+
+impl<T: Value + Clone> PartialView<T> {
+    /// Attempt to make progress using the variables resolved so far
+    ///
+    /// Once this returns [`Step::Complete`] the view has no dependencies left
+    /// and should be discarded
+    pub fn try_step(
+        &mut self,
+        known: &HashMap<Var, T>,
+    ) -> Result<Step<T>, T::Error> {
+        let placeholder = Partial {
+            var: self.0.var,
+            recursive: self.0.recursive,
+            component: self.0.component,
+            cycle_size: self.0.cycle_size,
+            result: None,
+            dependencies: HashSet::new(),
+        };
+        let partial = mem::replace(&mut self.0, placeholder);
+        match partial
+            .try_resolve(|dep| known.get(&dep).cloned(), &ByValue)
+            .map_err(Failure::into_inner)?
+        {
+            TryResolveResult::Complete(result, _) => Ok(Step::Complete(result)),
+            TryResolveResult::Incomplete(partial, progressed) => {
+                self.0 = partial;
+                Ok(Step::Incomplete(progressed))
+            }
+        }
+    }
+}
+
+/// An error raised while resolving a variable, tagged with where it came from
+pub(super) enum Failure<E> {
+    /// Raised by [`Strategy::merge`]
+    Merge(E),
+    /// Raised by [`Strategy::resolve_cycle`] for the variable
+    Cycle(Var, E),
+}
+
+impl<E> Failure<E> {
+    pub(super) fn into_inner(self) -> E {
+        match self {
+            Failure::Merge(e) | Failure::Cycle(_, e) => e,
+        }
+    }
+}
+
+impl<E: std::error::Error> From<Failure<E>> for Error<E> {
+    fn from(failure: Failure<E>) -> Self {
+        match failure {
+            Failure::Merge(e) => Error::Custom(e),
+            Failure::Cycle(var, source) => Error::Cycle { var, source },
+        }
+    }
+}
+
+enum TryResolveResult<T> {
+    // True if the value came from Strategy::resolve_cycle
+    Complete(T, bool),
+    Incomplete(Partial<T>, bool),
+}
+
+impl<T> Partial<T> {
+    // `fetch` produces the value of a dependency, if it has been resolved
+    fn try_resolve<S: Strategy<T>>(
+        self,
+        mut fetch: impl FnMut(Var) -> Option<T>,
+        strategy: &S,
+    ) -> Result<TryResolveResult<T>, Failure<S::Error>> {
+        let Self {
+            var,
+            recursive,
+            component,
+            cycle_size,
+            result,
+            dependencies,
+        } = self;
+        let mut new_result = None;
+        let mut new_dependencies = HashSet::new();
+        // Merge in ascending order, see Resolution::pass
+        let mut dependencies = dependencies.into_iter().collect::<Vec<_>>();
+        dependencies.sort_unstable_by_key(|Var(var)| *var);
+        for dep in dependencies {
+            // If we have a value for the variable we merge it into the result,
+            // otherwise it goes back in the dependency set
+            if let Some(known) = fetch(dep) {
+                new_result =
+                    merge_opt(strategy, var, new_result, Some((dep, known)))
+                        .map_err(Failure::Merge)?;
+            } else {
+                let _ = new_dependencies.insert(dep);
+            }
+        }
+
+        // If new_result contains something then we learned something new from
+        // this pass
+        let progressed = new_result.is_some();
+        let result = merge_opt(strategy, var, result, new_result)
+            .map_err(Failure::Merge)?;
+
+        // If we still have dependencies to resolve the result is always
+        // Incomplete
+        if !new_dependencies.is_empty() {
+            return Ok(TryResolveResult::Incomplete(
+                Self {
+                    var,
+                    recursive,
+                    component,
+                    cycle_size,
+                    result,
+                    dependencies: new_dependencies,
+                },
+                progressed,
+            ));
+        }
+
+        // If our last remaining dependency is a recursive edge we can ask the
+        // strategy what the answer should be
+        let result = result.map(|(_, value)| value);
+        let result = if recursive {
+            strategy
+                .resolve_cycle(result, cycle_size)
+                .map_err(|e| Failure::Cycle(var, e))?
+        } else {
+            result
+        };
+
+        // Finally if we don't have a result then we're stuck. Nothing more
+        // will be learned about this variable, the resolve loop will report
+        // it once nothing else makes progress either
+        let Some(result) = result else {
+            return Ok(TryResolveResult::Incomplete(
+                Self {
+                    var,
+                    recursive,
+                    component,
+                    cycle_size,
+                    result: None,
+                    dependencies: HashSet::new(),
+                },
+                false,
+            ));
+        };
+
+        Ok(TryResolveResult::Complete(result, recursive))
+    }
+}
+
+// Each value is paired with the dependency it came from, the merged value
+// keeps the left hand source
+fn merge_opt<T, S: Strategy<T>>(
+    strategy: &S,
+    target: Var,
+    left: Option<(Var, T)>,
+    right: Option<(Var, T)>,
+) -> Result<Option<(Var, T)>, S::Error> {
+    match (left, right) {
+        (None, None) => Ok(None),
+        (Some(left), None) => Ok(Some(left)),
+        (None, Some(right)) => Ok(Some(right)),
+        (Some((source, left)), Some((other, right))) => {
+            let context = MergeContext {
+                target,
+                left: source,
+                right: other,
+            };
+            Ok(Some((source, strategy.merge(left, right, context)?)))
+        }
+    }
+}
+
+/// How the resolver combines the values of dependencies
+pub(super) trait Strategy<T> {
+    type Error: std::error::Error;
+
+    fn merge(
+        &self,
+        left: T,
+        right: T,
+        context: MergeContext,
+    ) -> Result<T, Self::Error>;
+
+    /// Produce the value for a cycle, or None if it can't be decided
+    fn resolve_cycle(
+        &self,
+        known: Option<T>,
+        cycle_size: usize,
+    ) -> Result<Option<T>, Self::Error>;
+}
+
+/// Defer to the [`Value`] implementation
+pub(super) struct ByValue;
+
+impl<T: Value> Strategy<T> for ByValue {
+    type Error = T::Error;
+
+    fn merge(
+        &self,
+        left: T,
+        right: T,
+        context: MergeContext,
+    ) -> Result<T, Self::Error> {
+        T::merge_with_context(left, right, context)
+    }
+
+    fn resolve_cycle(
+        &self,
+        known: Option<T>,
+        cycle_size: usize,
+    ) -> Result<Option<T>, Self::Error> {
+        Ok(Some(T::resolve_cycle(known, cycle_size)?))
+    }
+}
+
+/// Keep the greater value according to the wrapped comparison
+pub(super) struct ByOrdering<F>(pub(super) F);
+
+impl<T, F: Fn(&T, &T) -> Ordering> Strategy<T> for ByOrdering<F> {
+    type Error = Infallible;
+
+    fn merge(
+        &self,
+        left: T,
+        right: T,
+        _: MergeContext,
+    ) -> Result<T, Self::Error> {
+        match (self.0)(&left, &right) {
+            Ordering::Less => Ok(right),
+            Ordering::Equal | Ordering::Greater => Ok(left),
+        }
+    }
+
+    fn resolve_cycle(
+        &self,
+        known: Option<T>,
+        _: usize,
+    ) -> Result<Option<T>, Self::Error> {
+        Ok(known)
+    }
+}
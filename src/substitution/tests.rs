@@ -1 +1,2 @@
+mod table;
 mod trait_inference;
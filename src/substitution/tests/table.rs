@@ -0,0 +1,1116 @@
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+};
+
+use crate::substitution::{
+    CycleError, Error, MergeContext, MergeOutcome, SelfDependencyError,
+    SelfDependencyPolicy, Step, Table, Value, Var,
+};
+
+type Result<T, E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
+
+#[test]
+fn resolve_by_max() -> Result<()> {
+    let mut table = Table::new();
+    let leaves = [table.var(), table.var(), table.var()];
+    let [a, b, c] = [table.var(), table.var(), table.var()];
+    for (leaf, value) in leaves.into_iter().zip([3, 7, 5]) {
+        table.fact(leaf, value)?;
+    }
+    // a takes the max of the leaves
+    for leaf in leaves {
+        table.dependency(a, leaf);
+    }
+    // b and c form a cycle hanging off of a and the first leaf
+    table.dependency(b, c);
+    table.dependency(c, b);
+    table.dependency(b, a);
+    table.dependency(c, leaves[0]);
+
+    let result = table.resolve_by(Ord::cmp)?;
+
+    let expected = HashMap::from([
+        (leaves[0], 3),
+        (leaves[1], 7),
+        (leaves[2], 5),
+        (a, 7),
+        (b, 7),
+        (c, 7),
+    ]);
+    assert_eq!(result, expected);
+    Ok(())
+}
+
+#[test]
+fn memory_usage() -> Result<()> {
+    let mut table = Table::new();
+    let empty = table.memory_usage();
+    let mut previous = table.var();
+    table.fact(previous, 0)?;
+    for i in 1..1000 {
+        let var = table.var();
+        if i % 2 == 0 {
+            table.fact(var, i)?;
+        } else {
+            table.dependency(var, previous);
+        }
+        previous = var;
+    }
+    assert!(table.memory_usage() > empty);
+
+    // Labels count too
+    let mut plain = Table::<bool>::new();
+    let _ = plain.var();
+    let mut labeled = Table::<bool>::new();
+    let _ = labeled.var_labeled("a fairly long label");
+    assert!(labeled.memory_usage() > plain.memory_usage());
+    Ok(())
+}
+
+#[test]
+fn with_capacity() -> Result<()> {
+    let mut table = Table::with_capacity(100);
+    assert!(table.memory_usage() > Table::<bool>::new().memory_usage());
+    let a = table.var();
+    let b = table.var();
+    table.fact(a, true)?;
+    table.dependency(b, a);
+    assert!(table.resolve()?[&b]);
+    Ok(())
+}
+
+#[test]
+fn build_without_clone() -> Result<()> {
+    struct NotClone;
+
+    let mut table = Table::with_undo();
+    let a = table.var();
+    let b = table.var();
+    table.fact(a, NotClone)?;
+    table.dependency(b, a);
+    assert_eq!(table.facts().count(), 1);
+    assert!(table.undo());
+    Ok(())
+}
+
+#[test]
+fn debug() -> Result<()> {
+    let mut table = Table::new();
+    let a = table.var();
+    let b = table.var();
+    let c = table.var();
+    table.fact(a, true)?;
+    table.dependency(c, b);
+    table.dependency(c, a);
+    assert_eq!(
+        format!("{table:?}"),
+        "Table { vars: 3, facts: {Var(0): true}, dependencies: {Var(2): \
+         [Var(0), Var(1)]}, .. }"
+    );
+    Ok(())
+}
+
+#[test]
+fn build_plan() -> Result<()> {
+    // Same shape as the tree test in trait_inference
+    let mut table = Table::new();
+    let nodes = [(); 6].map(|()| table.var());
+    for (parent, children) in [(0, [1, 2]), (1, [3, 4]), (2, [4, 5])] {
+        for child in children {
+            table.dependency(nodes[parent], nodes[child]);
+        }
+    }
+    for (leaf, value) in [(3, true), (4, true), (5, false)] {
+        table.fact(nodes[leaf], value)?;
+    }
+
+    let plan = table.build_plan()?;
+
+    assert_eq!(3, plan.len());
+    let position = |var| plan.iter().position(|&(v, _)| v == var);
+    for (var, dependencies) in &plan {
+        for &dependency in dependencies {
+            if let Some(before) = position(dependency) {
+                assert!(before < position(*var).unwrap());
+            }
+        }
+    }
+    let root = plan.iter().find(|&&(var, _)| var == nodes[0]);
+    assert_eq!(Some(&(nodes[0], vec![nodes[1], nodes[2]])), root);
+
+    table.dependency(nodes[1], nodes[0]);
+    assert_eq!(
+        Err(CycleError(HashSet::from([nodes[0], nodes[1]]))),
+        table.build_plan()
+    );
+    Ok(())
+}
+
+#[test]
+fn topological_order() {
+    let mut table = Table::<bool>::new();
+    let [a, b, c, d, leaf] = [(); 5].map(|()| table.var());
+    table.dependency(a, b);
+    table.dependency(b, c);
+    table.dependency(c, b);
+    table.dependency(b, leaf);
+    table.dependency(d, leaf);
+    table.dependency(d, a);
+
+    let order = table.topological_order();
+
+    assert_eq!(
+        vec![
+            HashSet::from([leaf]),
+            HashSet::from([b, c]),
+            HashSet::from([a]),
+            HashSet::from([d]),
+        ],
+        order
+    );
+}
+
+#[test]
+fn dependency_components() {
+    let mut table = Table::<bool>::new();
+    let [a, b, c, d] = [table.var(), table.var(), table.var(), table.var()];
+    table.dependency(a, b);
+    table.dependency(b, a);
+    table.dependency(c, a);
+    table.dependency(d, d);
+
+    let mut components = table.dependency_components();
+    components.sort_unstable_by_key(HashSet::len);
+    assert_eq!(3, components.len());
+    assert!(components.contains(&HashSet::from([c])));
+    assert!(components.contains(&HashSet::from([d])));
+    assert_eq!(HashSet::from([a, b]), components[2]);
+}
+
+fn cyclic_table() -> Result<(Table<bool>, [Var; 4])> {
+    let mut table = Table::new();
+    let [a, b, c, d] = [table.var(), table.var(), table.var(), table.var()];
+    table.fact(a, false)?;
+    table.dependency(b, c);
+    table.dependency(c, b);
+    table.dependency(c, a);
+    table.dependency(d, b);
+    Ok((table, [a, b, c, d]))
+}
+
+#[test]
+fn resolve_with_cycles() -> Result<()> {
+    let (mut table, [a, b, c, _]) = cyclic_table()?;
+    // Two loops sharing the middle variable
+    let [left, middle, right] = [(); 3].map(|()| table.var());
+    table.dependency(left, middle);
+    table.dependency(middle, left);
+    table.dependency(middle, right);
+    table.dependency(right, middle);
+    table.dependency(right, a);
+
+    let (_, cycles) = table.resolve_with_cycles()?;
+    assert_eq!(vec![vec![b, c], vec![left, middle, right, middle]], cycles);
+    Ok(())
+}
+
+#[test]
+fn re_resolve() -> Result<()> {
+    let (table, [a, _, _, _]) = cyclic_table()?;
+    let mut resolved = table.into_resolved();
+    resolved.re_resolve()?;
+    assert_eq!(&cyclic_table()?.0.resolve()?, resolved.values());
+
+    // Unaffected by the edit below
+    let [e, f] = [(); 2].map(|()| resolved.var());
+    assert_eq!(None, resolved.fact(e, false));
+    resolved.dependency(f, e);
+    resolved.re_resolve()?;
+    assert_eq!(Some(&false), resolved.values().get(&f));
+
+    assert_eq!(Some(false), resolved.fact(a, true));
+    resolved.re_resolve()?;
+    let (mut expected, _) = cyclic_table()?;
+    let _ = expected.retract(a);
+    expected.fact(a, true)?;
+    expected.fact(e, false)?;
+    expected.dependency(f, e);
+    assert_eq!(&expected.resolve()?, resolved.values());
+    Ok(())
+}
+
+#[test]
+fn re_resolve_after_error() -> Result<()> {
+    let mut table = Table::new();
+    let [a, b, c] = [(); 3].map(|()| table.var());
+    table.fact(a, Max(1))?;
+    table.dependency(b, c);
+    table.dependency(c, b);
+    let mut resolved = table.into_resolved();
+    assert!(resolved.re_resolve().is_err());
+    assert_eq!(Some(&Max(1)), resolved.values().get(&a));
+    assert_eq!(None, resolved.values().get(&b));
+
+    // Gives the cycle a base case
+    resolved.dependency(c, a);
+    resolved.re_resolve()?;
+    assert_eq!(Some(&Max(1)), resolved.values().get(&b));
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn resolve_parallel() -> Result<()> {
+    fn table() -> Result<Table<bool>> {
+        let (mut table, _) = cyclic_table()?;
+        // Independent of the cycle in cyclic_table
+        let [e, f, g, lone] = [(); 4].map(|()| table.var());
+        table.fact(e, true)?;
+        table.dependency(f, e);
+        table.dependency(g, f);
+        table.fact(lone, false)?;
+        Ok(table)
+    }
+
+    assert_eq!(table()?.resolve()?, table()?.resolve_parallel()?);
+    Ok(())
+}
+
+#[test]
+fn resolve_from_condensation() -> Result<()> {
+    let (table, _) = cyclic_table()?;
+    let condensation = table.condense();
+    let result = table.resolve_from_condensation(&condensation)?;
+    assert_eq!(result, cyclic_table()?.0.resolve()?);
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "Condensation doesn't match")]
+#[cfg(debug_assertions)]
+fn resolve_from_stale_condensation() {
+    let Ok((mut table, [_, b, _, d])) = cyclic_table() else {
+        panic!("Failed to build table")
+    };
+    let condensation = table.condense();
+    // Pulls d into the cycle
+    table.dependency(b, d);
+    let _ = table.resolve_from_condensation(&condensation);
+}
+
+#[test]
+fn undo() -> Result<()> {
+    let mut table = Table::with_undo();
+    let a = table.var();
+    let b = table.var();
+    table.fact(a, true)?;
+    table.dependency(b, a);
+
+    assert!(table.undo());
+    assert!(table.undo());
+    assert!(table.known.is_empty());
+    assert!(table.unknown.is_empty());
+
+    // Then the two vars
+    assert_eq!(2, table.var_count());
+    assert!(table.undo());
+    assert!(table.undo());
+    assert_eq!(0, table.var_count());
+    assert_eq!(table.var(), a);
+    Ok(())
+}
+
+#[test]
+fn undo_restores_shadowed_dependencies() -> Result<()> {
+    let mut table = Table::with_undo();
+    let a = table.var();
+    let b = table.var();
+    table.dependency(a, b);
+    table.fact(a, true)?;
+
+    assert!(table.undo());
+    assert!(table.known.is_empty());
+    assert_eq!(table.unknown, HashMap::from([(a, HashSet::from([b]))]));
+    Ok(())
+}
+
+#[test]
+fn fact_merge() -> Result<()> {
+    let mut table = Table::with_undo();
+    let a = table.var();
+    let b = table.var();
+    table.dependency(a, b);
+    table.fact_merge(a, true)?;
+    table.fact_merge(a, false)?;
+    assert_eq!(table.known, HashMap::from([(a, false)]));
+    // The dependency is still superceded
+    assert!(table.unknown.is_empty());
+
+    assert!(table.undo());
+    assert_eq!(table.known, HashMap::from([(a, true)]));
+    Ok(())
+}
+
+#[test]
+fn self_dependency_allow() {
+    let mut table = Table::<bool>::new();
+    let a = table.var();
+    assert_eq!(table.try_dependency(a, a), Ok(()));
+    assert_eq!(table.unknown, HashMap::from([(a, HashSet::from([a]))]));
+}
+
+#[test]
+fn self_dependency_ignore() {
+    let mut table = Table::<bool>::new()
+        .with_self_dependency_policy(SelfDependencyPolicy::Ignore);
+    let a = table.var();
+    let b = table.var();
+    table.dependency(a, a);
+    table.dependency(a, b);
+    assert_eq!(table.unknown, HashMap::from([(a, HashSet::from([b]))]));
+}
+
+#[test]
+fn self_dependency_error() {
+    let mut table = Table::<bool>::new()
+        .with_self_dependency_policy(SelfDependencyPolicy::Error);
+    let a = table.var();
+    assert_eq!(table.try_dependency(a, a), Err(SelfDependencyError(a)));
+    assert!(table.unknown.is_empty());
+}
+
+#[test]
+fn undo_disabled() {
+    let mut table = Table::<bool>::new();
+    let _ = table.var();
+    assert!(!table.undo());
+}
+
+#[test]
+fn no_progress_reports_stuck_vars() {
+    let mut table = Table::<usize>::new();
+    let a = table.var();
+    let b = table.var();
+    let c = table.var();
+    // b has nothing to resolve from, so a and c can't resolve either
+    table.dependency(a, b);
+    table.dependency(c, a);
+
+    let Err(Error::NoProgress { stuck, labels }) = table.resolve_by(Ord::cmp)
+    else {
+        panic!("Expected NoProgress")
+    };
+    let expected = HashMap::from([
+        (a, HashSet::from([b])),
+        (b, HashSet::new()),
+        (c, HashSet::from([a])),
+    ]);
+    assert_eq!(stuck, expected);
+    assert_eq!(
+        Error::<Infallible>::NoProgress { stuck, labels }.to_string(),
+        "Substitution stopped making progress, 3 variables unresolved \
+         (Var(0), Var(1), Var(2))"
+    );
+}
+
+#[test]
+fn labeled_vars() {
+    let mut table = Table::<usize>::new();
+    let a = table.var_labeled("a");
+    let b = table.var();
+    let c = table.var_labeled("c");
+    table.dependency(a, b);
+    table.dependency(c, a);
+    assert_eq!(Some("a"), table.label(a));
+    assert_eq!(None, table.label(b));
+    assert_eq!(
+        format!("{table:?}"),
+        "Table { vars: 3, facts: {}, dependencies: {Var(0): [Var(1)], \
+         Var(2): [Var(0)]}, labels: {Var(0): \"a\", Var(2): \"c\"}, .. }"
+    );
+
+    let Err(error) = table.resolve_by(Ord::cmp) else {
+        panic!("Expected NoProgress")
+    };
+    assert_eq!(
+        error.to_string(),
+        "Substitution stopped making progress, 3 variables unresolved \
+         (Var(0) \"a\", Var(1), Var(2) \"c\")"
+    );
+}
+
+#[test]
+fn resolve_bounded() -> Result<()> {
+    fn chain() -> Result<Table<bool>, Box<dyn std::error::Error>> {
+        let mut table = Table::new();
+        let mut previous = table.var();
+        table.fact(previous, true)?;
+        for _ in 0..10 {
+            let var = table.var();
+            table.dependency(var, previous);
+            previous = var;
+        }
+        Ok(table)
+    }
+
+    let Err(Error::IterationLimit { passes }) = chain()?.resolve_bounded(0)
+    else {
+        panic!("Expected IterationLimit")
+    };
+    assert_eq!(passes, 0);
+    // Each pass resolves at least one more link in the chain
+    assert_eq!(chain()?.resolve_bounded(10)?, chain()?.resolve()?);
+    Ok(())
+}
+
+#[test]
+fn resolve_stream() -> Result<()> {
+    let mut table = Table::new();
+    let [a, b, c, d] = [table.var(), table.var(), table.var(), table.var()];
+    table.fact(a, Max(1))?;
+    table.dependency(b, a);
+    table.dependency(c, b);
+    // No base case
+    table.dependency(d, d);
+
+    let mut stream = table.resolve_stream();
+    assert!(matches!(stream.next(), Some(Ok((var, Max(1)))) if var == a));
+    // b and c are resolved in the same pass which then fails on d
+    assert!(matches!(stream.next(), Some(Ok((var, Max(1)))) if var == b));
+    assert!(matches!(stream.next(), Some(Ok((var, Max(1)))) if var == c));
+    assert!(matches!(
+        stream.next(),
+        Some(Err(Error::Cycle { var, source: NoBaseCase })) if var == d
+    ));
+    assert!(stream.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn resolve_hybrid() -> Result<()> {
+    let mut table = Table::new();
+    let leaves = [table.var(), table.var(), table.var(), table.var()];
+    let [a, b] = [table.var(), table.var()];
+    // Half the leaves are known up front
+    table.fact(leaves[0], Max(1))?;
+    table.fact(leaves[1], Max(4))?;
+    // Both a and b depend on every leaf so each lazy leaf is needed twice
+    for leaf in leaves {
+        table.dependency(a, leaf);
+        table.dependency(b, leaf);
+    }
+
+    let mut calls = HashMap::<Var, usize>::new();
+    let result = table.resolve_hybrid(|var| {
+        *calls.entry(var).or_default() += 1;
+        Some(if var == leaves[2] { Max(3) } else { Max(6) })
+    })?;
+
+    assert_eq!(HashMap::from([(leaves[2], 1), (leaves[3], 1)]), calls);
+    assert_eq!(Max(3), result[&leaves[2]]);
+    assert_eq!(Max(6), result[&a]);
+    assert_eq!(Max(6), result[&b]);
+    Ok(())
+}
+
+// a resolves from a fact on the first pass, conflict only sees the value it
+// disagrees with on the second
+fn conflicting_table() -> Result<(Table<Exact>, Var, Var)> {
+    let mut table = Table::new();
+    let [a, fact, conflict, late, late_fact] = [(); 5].map(|()| table.var());
+    table.fact(fact, Exact(2))?;
+    table.fact(late_fact, Exact(3))?;
+    table.dependency(a, fact);
+    table.dependency(conflict, fact);
+    table.dependency(conflict, late);
+    table.dependency(late, late_fact);
+    Ok((table, a, conflict))
+}
+
+#[test]
+fn resolve_var() -> Result<()> {
+    let (table, _, _) = conflicting_table()?;
+    assert!(matches!(table.resolve(), Err(Error::Custom(_))));
+
+    // Stops before reaching the conflict
+    let (table, a, _) = conflicting_table()?;
+    assert_eq!(Exact(2), table.resolve_var(a)?);
+
+    let (table, _, conflict) = conflicting_table()?;
+    assert!(matches!(table.resolve_var(conflict), Err(Error::Custom(_))));
+    Ok(())
+}
+
+thread_local! {
+    static CLONES: Cell<usize> = const { Cell::new(0) };
+}
+
+// Counts how many times it is cloned on the current thread
+#[derive(Debug, PartialEq)]
+struct Counted(u32);
+
+impl Clone for Counted {
+    fn clone(&self) -> Self {
+        CLONES.set(CLONES.get() + 1);
+        Counted(self.0)
+    }
+}
+
+impl Value for Counted {
+    type Error = Infallible;
+
+    fn merge(left: Self, right: Self) -> Result<Self, Self::Error> {
+        Ok(Counted(left.0.max(right.0)))
+    }
+
+    fn resolve_cycle(
+        known: Option<Self>,
+        _: usize,
+    ) -> Result<Self, Self::Error> {
+        Ok(known.unwrap_or(Counted(0)))
+    }
+}
+
+// a depends on fact through a chain of variables, with an extra route
+// through shared if it is set
+fn chain(shared: bool) -> Result<(Table<Counted>, Var)> {
+    let mut table = Table::new();
+    let [a, b, c, fact, d] = [(); 5].map(|()| table.var());
+    table.fact(fact, Counted(1))?;
+    table.dependency(a, b);
+    table.dependency(b, c);
+    table.dependency(c, fact);
+    if shared {
+        table.dependency(a, d);
+        table.dependency(d, fact);
+    }
+    Ok((table, a))
+}
+
+#[test]
+fn resolve_var_moves_values() -> Result<()> {
+    let (table, a) = chain(false)?;
+    assert_eq!(Counted(1), table.resolve_var(a)?);
+    assert_eq!(0, CLONES.get());
+
+    // Shared values are still cloned, the last dependent gets the original
+    let (table, a) = chain(true)?;
+    assert_eq!(Counted(1), table.resolve_var(a)?);
+    assert_eq!(1, CLONES.get());
+
+    // Every value is returned so each dependent gets a copy
+    let (table, _) = chain(false)?;
+    CLONES.set(0);
+    let _ = table.resolve()?;
+    assert_eq!(3, CLONES.get());
+    Ok(())
+}
+
+#[test]
+fn resolve_cloned() -> Result<()> {
+    let mut table = Table::new();
+    let [a, b, c] = [table.var(), table.var(), table.var()];
+    table.fact(a, Max(1))?;
+    table.dependency(b, a);
+    let first = table.resolve_cloned()?;
+    assert_eq!(Max(1), first[&b]);
+    assert!(!first.contains_key(&c));
+
+    // The table is still usable
+    table.fact(c, Max(5))?;
+    table.dependency(b, c);
+    let second = table.resolve_cloned()?;
+    assert_eq!(Max(5), second[&b]);
+    assert_eq!(second, table.resolve()?);
+    Ok(())
+}
+
+#[test]
+fn resolve_single_pass() -> Result<()> {
+    let mut table = Table::new();
+    // top is visited before middle in each pass
+    let [top, middle, leaf] = [table.var(), table.var(), table.var()];
+    table.fact(leaf, Max(3))?;
+    table.dependency(middle, leaf);
+    table.dependency(top, middle);
+
+    let first = table.resolve_single_pass();
+    assert_eq!(Max(3), first.resolved[&middle]);
+    assert_eq!(
+        HashMap::from([(top, HashSet::from([middle]))]),
+        first.residual
+    );
+    assert!(first.errors.is_empty());
+
+    // Feed the result of the last pass back in
+    table.fact(middle, first.resolved[&middle])?;
+    let second = table.resolve_single_pass();
+    assert_eq!(Max(3), second.resolved[&top]);
+    assert!(second.residual.is_empty());
+    Ok(())
+}
+
+#[test]
+fn remove_dependency() -> Result<()> {
+    let mut table = Table::new();
+    let [a, b, c] = [table.var(), table.var(), table.var()];
+    table.fact(b, Max(1))?;
+    table.fact(c, Max(9))?;
+    table.dependency(a, b);
+    table.dependency(a, c);
+
+    assert!(table.remove_dependency(a, c));
+    assert!(!table.remove_dependency(a, c));
+    assert!(!table.remove_dependency(b, a));
+    assert_eq!(Max(1), table.resolve_cloned()?[&a]);
+
+    // The last dependency takes the entry with it
+    assert!(table.remove_dependency(a, b));
+    assert_eq!(0, table.dependencies().count());
+    Ok(())
+}
+
+#[test]
+fn resolve_dense() -> Result<()> {
+    let mut table = Table::new();
+    let [a, _, b, _] = [(); 4].map(|()| table.var());
+    table.fact(a, Max(1))?;
+    table.dependency(b, a);
+
+    assert_eq!(
+        vec![Some(Max(1)), None, Some(Max(1)), None],
+        table.resolve_dense()?
+    );
+
+    // Not produced by this table so there's no entry for it
+    let mut table = Table::new();
+    let a = table.var();
+    table.fact(a, Max(1))?;
+    table.fact(Var(5), Max(2))?;
+    assert_eq!(vec![Some(Max(1))], table.resolve_dense()?);
+    Ok(())
+}
+
+#[test]
+fn resolve_renumbered() -> Result<()> {
+    let mut table = Table::new();
+    let [a, _, b, _, _, c] = [(); 6].map(|()| table.var());
+    table.fact(a, Max(1))?;
+    table.fact(c, Max(4))?;
+    table.dependency(b, a);
+    table.dependency(b, c);
+    let expected = table.resolve_cloned()?;
+
+    let (values, mapping) = table.resolve_renumbered()?;
+
+    assert_eq!(
+        HashMap::from([(a, Var(0)), (b, Var(1)), (c, Var(2))]),
+        mapping
+    );
+    for (old, new) in mapping {
+        assert_eq!(expected[&old], values[&new]);
+    }
+    Ok(())
+}
+
+#[test]
+fn retract() -> Result<()> {
+    let mut table = Table::new();
+    let a = table.var();
+    let b = table.var();
+    let c = table.var();
+    table.fact(b, false)?;
+    table.fact(c, true)?;
+    table.dependency(a, b);
+    table.fact(a, true)?;
+    // Dependencies added after the fact are also restored
+    table.dependency(a, c);
+
+    assert_eq!(table.retract(a), Some(true));
+    assert_eq!(table.retract(a), None);
+
+    let result = table.resolve()?;
+    assert!(!result[&a]);
+    Ok(())
+}
+
+#[test]
+fn inspect() -> Result<()> {
+    let mut table = Table::new();
+    let a = table.var();
+    let b = table.var();
+    let c = table.var();
+    table.fact(a, true)?;
+    table.dependency(b, a);
+    table.dependency(b, c);
+    // Superceded by the fact
+    table.dependency(a, c);
+
+    let facts = table.facts().collect::<HashMap<_, _>>();
+    assert_eq!(facts, HashMap::from([(a, &true)]));
+    let dependencies = table.dependencies().collect::<HashMap<_, _>>();
+    assert_eq!(dependencies, HashMap::from([(b, &HashSet::from([a, c]))]));
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("cycle has no base case")]
+struct NoBaseCase;
+
+// Keeps the largest value, cycles only resolve if they have a base case
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Max(u32);
+
+impl Value for Max {
+    type Error = NoBaseCase;
+
+    fn merge(left: Self, right: Self) -> Result<Self, Self::Error> {
+        Ok(Max(left.0.max(right.0)))
+    }
+
+    fn resolve_cycle(
+        known: Option<Self>,
+        _: usize,
+    ) -> Result<Self, Self::Error> {
+        known.ok_or(NoBaseCase)
+    }
+}
+
+#[test]
+fn resolve_partial() -> Result<()> {
+    fn table(
+        with_cycle: bool,
+    ) -> Result<(Table<Max>, [Var; 4]), Box<dyn std::error::Error>> {
+        let mut table = Table::new();
+        let [a, b, c, d] = [table.var(), table.var(), table.var(), table.var()];
+        table.fact(a, Max(1))?;
+        table.fact(b, Max(2))?;
+        table.dependency(c, a);
+        table.dependency(c, b);
+        if with_cycle {
+            // d can never be resolved
+            table.dependency(d, d);
+        } else {
+            table.dependency(d, c);
+        }
+        Ok((table, [a, b, c, d]))
+    }
+
+    let (partial, [a, b, c, d]) = table(true)?;
+    let (result, error) = partial.resolve_partial();
+    assert!(matches!(
+        error,
+        Some(Error::Cycle { var, source: NoBaseCase }) if var == d
+    ));
+    // c is visited before d so it is resolved before d fails
+    let expected = HashMap::from([(a, Max(1)), (b, Max(2)), (c, Max(2))]);
+    assert_eq!(result, expected);
+
+    let (result, error) = table(false)?.0.resolve_partial();
+    assert!(error.is_none());
+    assert_eq!(result, table(false)?.0.resolve()?);
+    assert_eq!(result[&d], Max(2));
+    Ok(())
+}
+
+#[test]
+fn manual_stepping() -> Result<()> {
+    fn table() -> Result<Table<bool>, Box<dyn std::error::Error>> {
+        let mut table = Table::new();
+        let [a, b, c, d, cycle] = [
+            table.var(),
+            table.var(),
+            table.var(),
+            table.var(),
+            table.var(),
+        ];
+        table.fact(a, true)?;
+        table.fact(b, false)?;
+        table.dependency(c, a);
+        table.dependency(d, c);
+        table.dependency(d, cycle);
+        table.dependency(cycle, d);
+        table.dependency(cycle, b);
+        Ok(table)
+    }
+
+    let (mut known, mut partials) = table()?.into_partials();
+    while !partials.is_empty() {
+        let mut progress = false;
+        for (var, mut partial) in std::mem::take(&mut partials) {
+            match partial.try_step(&known)? {
+                Step::Complete(value) => {
+                    let _ = known.insert(var, value);
+                    progress = true;
+                }
+                Step::Incomplete(progressed) => {
+                    let _ = partials.insert(var, partial);
+                    progress = progress || progressed;
+                }
+            }
+        }
+        assert!(progress, "Resolution stalled");
+    }
+
+    assert_eq!(known, table()?.resolve()?);
+    Ok(())
+}
+
+// Concatenates in merge order, so the result shows the order values were merged
+#[derive(Debug, Clone, PartialEq)]
+struct Concat(String);
+
+impl Value for Concat {
+    type Error = Infallible;
+
+    fn merge(left: Self, right: Self) -> Result<Self, Self::Error> {
+        Ok(Concat(left.0 + &right.0))
+    }
+
+    fn resolve_cycle(
+        known: Option<Self>,
+        _: usize,
+    ) -> Result<Self, Self::Error> {
+        Ok(known.unwrap_or_else(|| Concat(String::new())))
+    }
+}
+
+#[test]
+fn deterministic_merge_order() -> Result<()> {
+    let mut table = Table::new();
+    let result = table.var();
+    for name in ["a", "b", "c", "d", "e", "f"] {
+        let var = table.var();
+        table.fact(var, Concat(name.to_owned()))?;
+        table.dependency(result, var);
+    }
+    assert_eq!(table.resolve()?[&result], Concat("abcdef".to_owned()));
+    Ok(())
+}
+
+// Resolves to the size of the cycle it is part of, or 0 if it isn't
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CycleSize(usize);
+
+impl Value for CycleSize {
+    type Error = Infallible;
+
+    fn merge(left: Self, _: Self) -> Result<Self, Self::Error> {
+        Ok(left)
+    }
+
+    fn resolve_cycle(
+        _: Option<Self>,
+        cycle_size: usize,
+    ) -> Result<Self, Self::Error> {
+        Ok(CycleSize(cycle_size))
+    }
+}
+
+#[test]
+fn resolve_cycle_size() -> Result<()> {
+    let mut table = Table::<CycleSize>::new();
+    let [a, b, c, d, tail] = [
+        table.var(),
+        table.var(),
+        table.var(),
+        table.var(),
+        table.var(),
+    ];
+    table.dependency(a, b);
+    table.dependency(b, c);
+    table.dependency(c, a);
+    table.dependency(d, d);
+    // Not part of a larger cycle, so it is treated as a cycle of one
+    table.dependency(tail, c);
+
+    let result = table.resolve()?;
+    for var in [a, b, c] {
+        assert_eq!(result[&var], CycleSize(3));
+    }
+    assert_eq!(result[&d], CycleSize(1));
+    assert_eq!(result[&tail], CycleSize(1));
+    Ok(())
+}
+
+#[test]
+fn resolve_without_fact_or_dependencies() -> Result<()> {
+    // Neither a fact nor a dependency, resolve_cycle decides a value for b
+    let mut table = Table::<CycleSize>::new();
+    let [a, b] = [(); 2].map(|()| table.var());
+    table.dependency(a, b);
+    let result = table.resolve()?;
+    assert_eq!(CycleSize(1), result[&b]);
+    Ok(())
+}
+
+#[test]
+fn resolve_with_provenance() -> Result<()> {
+    let mut table = Table::new();
+    let [fact, a, b, c, tail] = [
+        table.var(),
+        table.var(),
+        table.var(),
+        table.var(),
+        table.var(),
+    ];
+    table.fact(fact, Max(2))?;
+    table.dependency(a, b);
+    table.dependency(b, a);
+    table.dependency(a, fact);
+    table.dependency(c, fact);
+    // Takes its value from the cycle without being part of it
+    table.dependency(tail, b);
+
+    let (result, cycles) = table.resolve_with_provenance()?;
+
+    assert_eq!(Max(2), result[&tail]);
+    // Every variable without a fact goes through resolve_cycle, whether or not
+    // it is part of a larger cycle
+    assert_eq!(HashSet::from([a, b, c, tail]), cycles);
+    assert!(!cycles.contains(&fact));
+    Ok(())
+}
+
+// Only merges equal values, conflicts report the variables involved
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Exact(u32);
+
+#[derive(Debug, thiserror::Error)]
+#[error("conflicting values")]
+struct Conflict(Option<MergeContext>);
+
+impl Value for Exact {
+    type Error = Conflict;
+
+    fn merge(left: Self, right: Self) -> Result<Self, Self::Error> {
+        if left == right {
+            Ok(left)
+        } else {
+            Err(Conflict(None))
+        }
+    }
+
+    fn merge_with_context(
+        left: Self,
+        right: Self,
+        context: MergeContext,
+    ) -> Result<Self, Self::Error> {
+        Self::merge(left, right).map_err(|_| Conflict(Some(context)))
+    }
+
+    fn resolve_cycle(
+        known: Option<Self>,
+        _: usize,
+    ) -> Result<Self, Self::Error> {
+        known.ok_or(Conflict(None))
+    }
+}
+
+#[test]
+fn merge_with_context() -> Result<()> {
+    let mut table = Table::new();
+    let [target, x, y, z] = [(); 4].map(|()| table.var());
+    table.fact(x, Exact(1))?;
+    table.fact(y, Exact(1))?;
+    table.fact(z, Exact(2))?;
+    for source in [x, y, z] {
+        table.dependency(target, source);
+    }
+
+    let Err(Error::Custom(Conflict(context))) = table.resolve() else {
+        panic!("Expected a conflict")
+    };
+
+    // x and y were merged first, so the merged value is blamed on x
+    assert_eq!(
+        Some(MergeContext {
+            target,
+            left: x,
+            right: z
+        }),
+        context
+    );
+    Ok(())
+}
+
+// Sums values of the same parity, values of the other parity are skipped
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Parity(u32);
+
+#[derive(Debug, thiserror::Error)]
+#[error("mixed parity")]
+struct MixedParity;
+
+impl Value for Parity {
+    type Error = MixedParity;
+
+    fn merge(left: Self, right: Self) -> Result<Self, Self::Error> {
+        if left.0 % 2 == right.0 % 2 {
+            Ok(Parity(left.0 + right.0))
+        } else {
+            Err(MixedParity)
+        }
+    }
+
+    fn merge_or_skip(
+        left: Self,
+        right: Self,
+    ) -> Result<MergeOutcome<Self>, Self::Error> {
+        Ok(Self::merge(left, right)
+            .map_or(MergeOutcome::Keep(left), MergeOutcome::Merged))
+    }
+
+    fn resolve_cycle(
+        known: Option<Self>,
+        _: usize,
+    ) -> Result<Self, Self::Error> {
+        known.ok_or(MixedParity)
+    }
+}
+
+#[test]
+fn merge_or_skip() -> Result<()> {
+    let mut table = Table::new();
+    let [target, x, y, z] = [(); 4].map(|()| table.var());
+    table.fact(x, Parity(2))?;
+    table.fact(y, Parity(3))?;
+    table.fact(z, Parity(4))?;
+    for source in [x, y, z] {
+        table.dependency(target, source);
+    }
+
+    // y doesn't combine with x so it is skipped
+    assert_eq!(Parity(6), table.resolve()?[&target]);
+    Ok(())
+}
+
+#[test]
+fn fact_checked() -> Result<()> {
+    let mut table = Table::new();
+    let [a, b, c, stuck, missing] = [(); 5].map(|()| table.var());
+    table.dependency(a, b);
+    table.dependency(b, c);
+    table.fact(c, Parity(2))?;
+    // Would stop resolution of the whole table, but a doesn't depend on it
+    table.dependency(stuck, missing);
+
+    // a resolves to Parity(2), which doesn't merge with an odd value
+    assert!(matches!(
+        table.fact_checked(a, Parity(3)),
+        Err(Error::Custom(MixedParity))
+    ));
+    assert!(!table.known.contains_key(&a));
+
+    table.fact_checked(a, Parity(4))?;
+    assert_eq!(Some(&Parity(4)), table.known.get(&a));
+    // The dependency is superceded as for fact
+    assert!(!table.unknown.contains_key(&a));
+
+    // Variables without dependencies are merged with any existing fact
+    table.fact_checked(c, Parity(6))?;
+    assert_eq!(Some(&Parity(8)), table.known.get(&c));
+    Ok(())
+}
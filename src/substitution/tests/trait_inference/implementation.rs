@@ -38,7 +38,10 @@ impl Value for bool {
     // In the event of a cyclic dependency we go with the result from the other
     // dependencies if present, and default to true if this is the only
     // dependency
-    fn resolve_cycle(known: Option<Self>) -> Result<Self, Self::Error> {
+    fn resolve_cycle(
+        known: Option<Self>,
+        _: usize,
+    ) -> Result<Self, Self::Error> {
         Ok(known.unwrap_or(true))
     }
 }
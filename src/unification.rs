@@ -1,20 +1,67 @@
 //! Unification table
 
-use std::{collections::HashMap, fmt::Debug, mem, ops::Range};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    cmp::{Ordering, Reverse},
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Display, Write as _},
+    hash::Hash,
+    mem,
+    ops::Range,
+    sync::PoisonError,
+};
 
 use ena::unify::{
-    InPlace, InPlaceUnificationTable, Snapshot, UnificationTable,
+    InPlace, InPlaceUnificationTable, Snapshot, UnificationTable, UnifyKey,
 };
+#[cfg(feature = "derive")]
+pub use pelican_derive::Unify;
 use value_type::value_type;
 
-pub use self::var::Var;
-use self::{value::Value, var::TypedVar};
+pub use self::{
+    builder::TableBuilder, history::Step, scheme::Scheme, state::TableState,
+    tuple::TupleError, var::Var,
+};
+use self::{
+    builder::{DepthLimit, Options},
+    deferred::{Deferred, Pending},
+    scheme::free_vars,
+    value::Value,
+    var::TypedVar,
+};
 
+mod builder;
+mod deferred;
+mod history;
+mod scheme;
+mod state;
+#[cfg(feature = "testing")]
+pub mod testing;
 #[cfg(test)]
 mod tests;
+mod tuple;
 mod value;
 mod var;
 
+type Constraint<T> = (ValueOrVar<T>, ValueOrVar<T>);
+// A constraint and its weight, see Table::constraint_weighted
+type Weighted<T> = (Constraint<T>, u32);
+// See Table::unify_with_unbound
+type WithUnbound<T> = (HashMap<Var, ValueOrVar<T>>, HashSet<Var>);
+// A weighted constraint and its span, see Table::constraint_spanned
+type Spanned<T, S> = (Weighted<T>, S);
+// See Table::into_unifier
+type IntoUnifier<T, S> = (
+    Vec<Var>,
+    Vec<Spanned<T, S>>,
+    Vec<(Deferred<T>, S)>,
+    Unifier<T>,
+);
+// See Table::unify_spanned
+type UnifySpanned<T, S> =
+    Result<HashMap<Var, ValueOrVar<T>>, SpannedError<<T as Unify>::Error, S>>;
+
 /// Defines how to unify two values in the table
 pub trait Unify: Debug + Clone {
     /// Error returned if unification fails
@@ -22,7 +69,8 @@ pub trait Unify: Debug + Clone {
 
     /// Unification strategy.
     ///
-    /// This method will be called once for each constraint added to the [`Table`]
+    /// This method will be called once for each constraint added to the [`Table`],
+    /// after both sides have been passed through [`Unify::normalize`]
     ///
     /// The [`Unifier`] argument provides methods for communicating with the
     /// unification engine
@@ -37,25 +85,142 @@ pub trait Unify: Debug + Clone {
     /// If unification tries to unify two sets which have both been resolved to
     /// concrete values, this method is called to produce the new value
     fn merge(left: &Self, right: &Self) -> Result<Self, Self::Error>;
+
+    /// Put a concrete value in a canonical form, e.g. sorting fields whose
+    /// order doesn't matter, so that [`merge`](Unify::merge) and
+    /// [`join`](Unify::join) can compare values structurally
+    ///
+    /// Applied to the concrete values passed to [`unify`](Unify::unify), after
+    /// [`normalize`](Unify::normalize), and to both values whenever two
+    /// variables with values are unified. The default implementation borrows
+    /// the value unchanged
+    fn canonicalize(&self) -> Cow<'_, Self> {
+        Cow::Borrowed(self)
+    }
+
+    /// Combine two concrete values which have met during
+    /// [`Table::unify_subtyping`], e.g. into their least upper bound
+    ///
+    /// The default implementation is [`Unify::merge`]
+    fn join(left: &Self, right: &Self) -> Result<Self, Self::Error> {
+        Self::merge(left, right)
+    }
+
+    /// Normalize one side of a constraint before it is passed to
+    /// [`Unify::unify`].
+    ///
+    /// The default implementation only resolves a top level variable to its
+    /// current value (see [`Unifier::normalize`]), override this to normalize
+    /// nested structure as well
+    fn normalize(
+        value: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> ValueOrVar<Self> {
+        unifier.normalize(value)
+    }
+
+    /// The values and variables nested directly inside this value, used by
+    /// [`Unifier::occurs`] and [`Table::generalize`]
+    ///
+    /// The default implementation has no children, so nothing is ever caught
+    /// by the occurs check
+    fn children(&self) -> impl Iterator<Item = &ValueOrVar<Self>> {
+        std::iter::empty()
+    }
+
+    /// Mutable version of [`Unify::children`], used by [`Unify::substitute`]
+    ///
+    /// The default implementation has no children
+    fn children_mut(&mut self) -> impl Iterator<Item = &mut ValueOrVar<Self>> {
+        std::iter::empty()
+    }
+
+    /// Format the value for [`pretty`], nested structure spanning several lines
+    /// should be indented by `indent` levels
+    ///
+    /// The default implementation is the value's [`Display`] output on one
+    /// line
+    fn fmt_pretty(&self, indent: usize) -> String
+    where
+        Self: Display,
+    {
+        let _ = indent;
+        self.to_string()
+    }
+
+    /// Replace the variables nested inside this value with their values from
+    /// the map returned by [`Table::unify`], see [`ValueOrVar::resolve_auto`]
+    ///
+    /// The default implementation resolves each of the value's
+    /// [children](Unify::children_mut) in place
+    #[must_use]
+    fn substitute(mut self, table: &HashMap<Var, ValueOrVar<Self>>) -> Self {
+        for child in self.children_mut() {
+            // Any variable will do as a placeholder while the child is moved
+            // out
+            let placeholder = ValueOrVar::Var(Var(0));
+            *child = mem::replace(child, placeholder).resolve_auto(table);
+        }
+        self
+    }
 }
 
 /// Unification table
+///
+/// Constraints can be tagged with a span of type `S`, e.g. their location in
+/// the source, to find out which constraint failed, see
+/// [`Table::unify_spanned`]
 #[expect(missing_debug_implementations)]
-pub struct Table<T: Unify> {
-    unification_table: InPlaceUnificationTable<TypedVar<T>>,
+pub struct Table<T: Unify, S = ()> {
+    // Only borrowed at runtime by Unifier::probe_shallow and methods taking
+    // &self, everything else goes through Table::table
+    unification_table: RefCell<InPlaceUnificationTable<TypedVar<T>>>,
     clean_snapshot: Snapshot<InPlace<TypedVar<T>>>,
-    constraints: Vec<(ValueOrVar<T>, ValueOrVar<T>)>,
+    constraints: Vec<Weighted<T>>,
+    // The span of each constraint, in the same order
+    spans: Vec<S>,
+    // Constraints added by Table::defer, with their spans
+    deferred: Vec<(Deferred<T>, S)>,
+    // Names given to variables by Table::named_var
+    names: HashMap<Var, String>,
+    // Whether any variable has been created above level 0, levels are only
+    // maintained when binding variables to values if so
+    leveled: bool,
+    // Set by Table::unify_subtyping so variables created while unifying use
+    // Unify::join too
+    subtyping: bool,
+    // Steps taken while unifying, only kept if the table was built with
+    // TableBuilder::history
+    history: Option<Vec<Step<T>>>,
+    // Each root linked under another root by the underlying table, in the
+    // order the links were made, see Table::dump_forest. ena doesn't expose
+    // its links and shortens them as it finds roots
+    links: Vec<(Var, Var)>,
+    // Calls to Unify::unify left, only set by Table::unify_with_budget
+    budget: Option<Budget>,
+    options: Options<T>,
+}
+
+// See Table::unify_with_budget
+struct Budget {
+    remaining: usize,
+    // Set once a call had to be skipped
+    exceeded: bool,
+}
+
+/// A point [`Table`] can be rolled back to, see [`Table::snapshot`]
+#[expect(missing_debug_implementations)]
+pub struct TableSnapshot<T: Unify> {
+    snapshot: Snapshot<InPlace<TypedVar<T>>>,
+    constraints: usize,
+    deferred: usize,
+    history: usize,
+    links: usize,
 }
 
 impl<T: Unify> Default for Table<T> {
     fn default() -> Self {
-        let mut unification_table = UnificationTable::new();
-        let clean_snapshot = unification_table.snapshot();
-        Self {
-            unification_table,
-            clean_snapshot,
-            constraints: Vec::new(),
-        }
+        Self::with_options(Options::default())
     }
 }
 
@@ -66,38 +231,902 @@ impl<T: Unify> Table<T> {
         Self::default()
     }
 
+    /// Construct a table whose variables are numbered from `start`, see
+    /// [`TableBuilder::var_offset`]
+    #[must_use]
+    pub fn with_var_offset(start: u32) -> Self {
+        Self::builder().var_offset(start).build()
+    }
+
+    /// Start configuring a table, see [`TableBuilder`]
+    #[must_use]
+    pub fn builder() -> TableBuilder<T> {
+        TableBuilder::new()
+    }
+
+    /// Derive constraints from an existing map, e.g. the result of
+    /// [`substitution::Table::resolve`](crate::substitution::Table::resolve)
+    ///
+    /// `generate` is called for each entry in the map and any constraints it
+    /// produces are returned, ready to be passed to [`Table::constraint`]
+    pub fn constraints_from<K, V>(
+        source: &HashMap<K, V>,
+        generate: impl Fn(&K, &V) -> Option<(ValueOrVar<T>, ValueOrVar<T>)>,
+    ) -> Vec<(ValueOrVar<T>, ValueOrVar<T>)> {
+        source
+            .iter()
+            .filter_map(|(key, value)| generate(key, value))
+            .collect()
+    }
+
+    /// Seed a table with the result of [`Table::unify`], e.g. to add more
+    /// constraints in a second phase of inference
+    ///
+    /// Variables are created up to the highest one mentioned in the map,
+    /// including inside values, then each variable is unified with the
+    /// variable or value it maps to. Each variable maps to one thing so a
+    /// class can't end up with two values and this can't fail
+    #[must_use]
+    pub fn from_substitution(map: HashMap<Var, ValueOrVar<T>>) -> Self {
+        let mut table = Self::new();
+        let offset = table.options.var_offset;
+        let end = map
+            .iter()
+            .flat_map(|(&var, value)| {
+                let mut vars = vec![var];
+                free_vars(value, &mut vars);
+                vars
+            })
+            .map(|Var(var)| var as usize + 1)
+            .max()
+            .unwrap_or(0);
+        let _ = table.vars(end);
+        let mut bindings = map.into_iter().collect::<Vec<_>>();
+        bindings.sort_by_key(|&(Var(var), _)| var);
+        for (var, value) in bindings {
+            let var = var.annotate(offset);
+            let result = match value {
+                ValueOrVar::Var(other) => {
+                    table.link(var, other.annotate(offset))
+                }
+                ValueOrVar::Value(value) => {
+                    table.table().unify_var_value(var, Value::bound(value))
+                }
+            };
+            let Ok(()) = result else {
+                unreachable!("A class can't end up with two values")
+            };
+        }
+        table
+    }
+
+    /// Unify `left` and `right` immediately rather than waiting for
+    /// [`unify`](Table::unify), e.g. to report the first type error as soon
+    /// as it is found
+    ///
+    /// Any [deferred](Table::defer) constraints the constraint makes ready
+    /// run as well, the rest wait for a later call or `unify`. If unification
+    /// fails anything the constraint changed is rolled back, including
+    /// deferred constraints which ran, and the error is returned. Constraints
+    /// added with [`constraint`](Table::constraint) are still only applied by
+    /// `unify`
+    pub fn constraint_now(
+        &mut self,
+        left: ValueOrVar<T>,
+        right: ValueOrVar<T>,
+    ) -> Result<(), T::Error> {
+        // The unifier needs to own the table, leave an empty one in its place
+        let table = mem::replace(self, Self::with_options(Options::default()));
+        let mut unifier = Unifier(table);
+        // Kept to put back if the constraint fails
+        let deferred = mem::take(&mut unifier.0.deferred);
+        let mut pending = Pending::new();
+        for (deferred, ()) in deferred.iter().cloned() {
+            pending.push(deferred, 0);
+        }
+        let snapshot = unifier.0.snapshot();
+        let result = pending
+            .step(&mut unifier, 0, (left, right))
+            .map_err(|(error, _)| error);
+        if result.is_ok() {
+            unifier.0.commit(snapshot);
+            unifier.0.deferred = pending
+                .into_deferred()
+                .map(|deferred| (deferred, ()))
+                .collect();
+        } else {
+            unifier.0.rollback_to(snapshot);
+            unifier.0.deferred = deferred;
+        }
+        *self = unifier.0;
+        result
+    }
+}
+
+impl<T: Unify, S> Table<T, S> {
+    /// Construct a table whose constraints are tagged with spans of type
+    /// `S`, see [`Table::constraint_spanned`]
+    #[must_use]
+    pub fn new_spanned() -> Self {
+        Self::with_options(Options::default())
+    }
+
+    pub(crate) fn with_options(options: Options<T>) -> Self {
+        let mut unification_table = UnificationTable::new();
+        let clean_snapshot = unification_table.snapshot();
+        Self {
+            unification_table: RefCell::new(unification_table),
+            clean_snapshot,
+            constraints: Vec::new(),
+            spans: Vec::new(),
+            deferred: Vec::new(),
+            names: HashMap::new(),
+            leveled: false,
+            subtyping: false,
+            history: options.history.then(Vec::new),
+            links: Vec::new(),
+            budget: None,
+            options,
+        }
+    }
+
     /// Create a fresh unification variable
+    ///
+    /// # Panics
+    /// If the table was built with a [variable
+    /// limit](TableBuilder::var_limit) and the limit has been reached
     pub fn var(&mut self) -> Var {
-        self.unification_table.new_key(None).erase()
+        self.try_var().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Create `n` fresh unification variables, in order
+    ///
+    /// # Panics
+    /// If the table was built with a [variable
+    /// limit](TableBuilder::var_limit) and the limit is reached
+    pub fn vars(&mut self, n: usize) -> Vec<Var> {
+        self.table().reserve(n);
+        (0..n).map(|_| self.var()).collect()
+    }
+
+    /// Create a fixed number of fresh unification variables, in order
+    ///
+    /// # Panics
+    /// If the table was built with a [variable
+    /// limit](TableBuilder::var_limit) and the limit is reached
+    pub fn fresh_array<const N: usize>(&mut self) -> [Var; N] {
+        self.table().reserve(N);
+        std::array::from_fn(|_| self.var())
+    }
+
+    /// Create a fresh unification variable with a name, used by
+    /// [`Table::fmt_var`]
+    ///
+    /// # Panics
+    /// If the table was built with a [variable
+    /// limit](TableBuilder::var_limit) and the limit has been reached
+    pub fn named_var(&mut self, name: impl Into<String>) -> Var {
+        let var = self.var();
+        let _ = self.names.insert(var, name.into());
+        var
+    }
+
+    /// Format a variable for display
+    ///
+    /// If the variable has been unified with others the name of the
+    /// representative variable is used, falling back to the variable's own
+    /// name and then to `?n`. This takes `&mut self` because finding the
+    /// representative updates the underlying table
+    pub fn fmt_var(&mut self, var: Var) -> String {
+        let offset = self.options.var_offset;
+        let root = self.table().find(var.annotate::<T>(offset)).erase(offset);
+        self.names
+            .get(&root)
+            .or_else(|| self.names.get(&var))
+            .cloned()
+            .unwrap_or_else(|| var.display_as("?").to_string())
+    }
+
+    /// Create a fresh unification variable, failing if the table was built with
+    /// a [variable limit](TableBuilder::var_limit) and the limit has been
+    /// reached
+    pub fn try_var(&mut self) -> Result<Var, VarLimitError> {
+        self.new_var(0)
+    }
+
+    /// Create a fresh unification variable at a level, for use with
+    /// [`Table::generalize_level`]. Variables created by [`Table::var`] are at
+    /// level 0
+    ///
+    /// When variables are unified the result takes the lowest of their levels,
+    /// and when a variable is unified with a value any variables inside the
+    /// value are lowered to its level
+    ///
+    /// # Panics
+    /// If the table was built with a [variable
+    /// limit](TableBuilder::var_limit) and the limit has been reached
+    pub fn var_at_level(&mut self, level: u32) -> Var {
+        // Everything is at level 0 until now, so there is nothing to lower
+        self.leveled |= level > 0;
+        self.new_var(level).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// The current level of a variable, see [`Table::var_at_level`]
+    pub fn level(&mut self, var: Var) -> u32 {
+        let offset = self.options.var_offset;
+        self.table().probe_value(var.annotate(offset)).level
+    }
+
+    fn new_var(&mut self, level: u32) -> Result<Var, VarLimitError> {
+        let offset = self.options.var_offset;
+        if let Some(limit) = self.options.var_limit
+            && self.table().len() >= limit as usize
+        {
+            return Err(VarLimitError(limit));
+        }
+        let mut value = Value::unbound(level);
+        value.subtyping = self.subtyping;
+        Ok(self.table().new_key(value).erase(offset))
     }
 
     /// Add a new constraint to the table
-    pub fn constraint(&mut self, left: ValueOrVar<T>, right: ValueOrVar<T>) {
-        self.constraints.push((left, right));
+    ///
+    /// The constraint has the maximum weight for
+    /// [`unify_min_conflict`](Table::unify_min_conflict) and the default span
+    pub fn constraint(&mut self, left: ValueOrVar<T>, right: ValueOrVar<T>)
+    where
+        S: Default,
+    {
+        self.constraint_weighted(left, right, u32::MAX);
+    }
+
+    /// Add a new constraint to the table tagged with a span, reported by
+    /// [`unify_spanned`](Table::unify_spanned) if the constraint fails
+    ///
+    /// The constraint has the maximum weight for
+    /// [`unify_min_conflict`](Table::unify_min_conflict)
+    pub fn constraint_spanned(
+        &mut self,
+        left: ValueOrVar<T>,
+        right: ValueOrVar<T>,
+        span: S,
+    ) {
+        self.constraints.push(((left, right), u32::MAX));
+        self.spans.push(span);
+    }
+
+    /// Add a new constraint to the table with a weight, used by
+    /// [`unify_min_conflict`](Table::unify_min_conflict) to decide which
+    /// constraints to drop
+    pub fn constraint_weighted(
+        &mut self,
+        left: ValueOrVar<T>,
+        right: ValueOrVar<T>,
+        weight: u32,
+    ) where
+        S: Default,
+    {
+        self.constraints.push(((left, right), weight));
+        self.spans.push(S::default());
+    }
+
+    /// Drop constraints which are structurally identical to one added earlier,
+    /// see [`TableBuilder::dedup`] to do this every time the table is unified
+    ///
+    /// The order of the remaining constraints is preserved, each keeps its
+    /// own weight and span. Deferred constraints are left alone. Rolling back
+    /// to a [snapshot](Table::snapshot) taken before this may drop the wrong
+    /// constraints
+    pub fn dedup_constraints(&mut self)
+    where
+        T: Eq + Hash,
+    {
+        let keep = builder::dedup(&self.constraints);
+        (self.constraints, self.spans) = mem::take(&mut self.constraints)
+            .into_iter()
+            .zip(mem::take(&mut self.spans))
+            .zip(keep)
+            .filter_map(|(constraint, keep)| keep.then_some(constraint))
+            .unzip();
+    }
+
+    /// Discard every constraint added so far
+    ///
+    /// Variables stay valid and keep any bindings made outside of
+    /// unification, e.g. by [`Table::import_partition`]
+    pub fn clear_constraints(&mut self) {
+        self.constraints.clear();
+        self.spans.clear();
+        self.deferred.clear();
+    }
+
+    /// Record the current state of the table so it can be restored by
+    /// [`rollback_to`](Table::rollback_to)
+    ///
+    /// Snapshots nest, each must be passed to either
+    /// [`rollback_to`](Table::rollback_to) or [`commit`](Table::commit), most
+    /// recent first
+    pub fn snapshot(&mut self) -> TableSnapshot<T> {
+        TableSnapshot {
+            snapshot: self.table().snapshot(),
+            constraints: self.constraints.len(),
+            deferred: self.deferred.len(),
+            history: self.history().len(),
+            links: self.links.len(),
+        }
+    }
+
+    /// Undo everything since `snapshot` was taken, including creating
+    /// variables and adding constraints
+    pub fn rollback_to(&mut self, snapshot: TableSnapshot<T>) {
+        self.table().rollback_to(snapshot.snapshot);
+        self.constraints.truncate(snapshot.constraints);
+        self.spans.truncate(snapshot.constraints);
+        self.deferred.truncate(snapshot.deferred);
+        if let Some(history) = &mut self.history {
+            history.truncate(snapshot.history);
+        }
+        self.links.truncate(snapshot.links);
+        let end = self.table().len() + self.options.var_offset as usize;
+        self.names.retain(|&Var(var), _| (var as usize) < end);
+    }
+
+    /// Keep everything since `snapshot` was taken
+    pub fn commit(&mut self, snapshot: TableSnapshot<T>) {
+        self.table().commit(snapshot.snapshot);
+    }
+
+    /// Iterate over the constraints added so far, in the order they were
+    /// added
+    pub fn constraints(
+        &self,
+    ) -> impl Iterator<Item = (&ValueOrVar<T>, &ValueOrVar<T>)> {
+        self.constraints
+            .iter()
+            .map(|((left, right), _)| (left, right))
+    }
+
+    /// The number of constraints added so far
+    #[must_use]
+    pub fn constraint_count(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// Export the equivalence classes of the variables in the table, without
+    /// any values they have been unified with
+    ///
+    /// Each variable is paired with the representative of its class, see
+    /// [`Table::import_partition`]
+    pub fn export_partition(&mut self) -> Vec<(Var, Var)> {
+        let offset = self.options.var_offset;
+        self.get_vars()
+            .into_iter()
+            .map(|var| {
+                let root = self.table().find(var.annotate::<T>(offset));
+                (var, root.erase(offset))
+            })
+            .collect()
+    }
+
+    /// Copy the table, e.g. to explore one of several alternatives and
+    /// discard it
+    ///
+    /// This is a deep copy of the variables, constraints and values so it is
+    /// O(n) in the size of the table, [`snapshot`](Table::snapshot) is cheaper
+    /// for exploring alternatives one at a time. Snapshots of this table can't
+    /// be used with the copy
+    #[must_use]
+    pub fn fork(&self) -> Self
+    where
+        S: Clone,
+    {
+        let offset = self.options.var_offset;
+        let mut fork = Self::with_options(self.options.clone());
+        let vars = self.get_vars();
+        let mut source = self.unification_table.borrow_mut();
+        let roots = vars
+            .iter()
+            .map(|&var| source.find(var.annotate::<T>(offset)).erase(offset))
+            .collect::<Vec<_>>();
+        // Only roots carry the value, the others pick it up when they're
+        // linked to their root
+        for (&var, &root) in vars.iter().zip(&roots) {
+            let value = source.probe_value(var.annotate(offset));
+            let value = if var == root {
+                value
+            } else {
+                Value::unbound(value.level)
+            };
+            let _ = fork.table().new_key(value);
+        }
+        for (var, root) in vars.into_iter().zip(roots) {
+            // Linking a lone variable to a root keeps the root as the
+            // representative, and an unbound value always merges
+            let Ok(()) = fork.link(var.annotate(offset), root.annotate(offset))
+            else {
+                unreachable!("Linking an unbound variable can't fail")
+            };
+        }
+        // The relinking above flattens every class, keep the links as they
+        // were made in this table
+        fork.links.clone_from(&self.links);
+        fork.constraints.clone_from(&self.constraints);
+        fork.spans.clone_from(&self.spans);
+        fork.deferred.clone_from(&self.deferred);
+        fork.names.clone_from(&self.names);
+        fork.leveled = self.leveled;
+        fork.subtyping = self.subtyping;
+        fork.history.clone_from(&self.history);
+        fork
+    }
+
+    /// Group every variable in the table by the representative of its
+    /// equivalence class, regardless of any value the class has been unified
+    /// with
+    ///
+    /// Each class lists its variables in ascending order, including the
+    /// representative itself
+    pub fn equivalence_classes(&mut self) -> HashMap<Var, Vec<Var>> {
+        let mut classes = HashMap::<_, Vec<_>>::new();
+        for (var, root) in self.export_partition() {
+            classes.entry(root).or_default().push(var);
+        }
+        classes
+    }
+
+    /// Summarise the shape of the union-find forest backing the table, e.g. to
+    /// check how it grows on a real workload
+    ///
+    /// Probes each variable once, which compresses its path to the root as
+    /// [`probe_shallow`](Unifier::probe_shallow) does. The chain lengths are
+    /// measured on the links recorded when classes were merged, see
+    /// [`dump_forest`](Table::dump_forest)
+    #[must_use]
+    pub fn stats(&self) -> UnifyStats {
+        let offset = self.options.var_offset;
+        let vars = self.get_vars();
+        let mut table = self.unification_table.borrow_mut();
+        let parents = self.links.iter().copied().collect::<HashMap<_, _>>();
+        let mut depths = HashMap::new();
+        let mut classes = HashSet::new();
+        let mut bound = 0;
+        let mut max_chain = 0;
+        for &var in &vars {
+            let key = var.annotate::<T>(offset);
+            let _ = classes.insert(table.find(key));
+            if table.probe_value(key).value.is_some() {
+                bound += 1;
+            }
+            max_chain = max_chain.max(depth(var, &parents, &mut depths));
+        }
+        UnifyStats {
+            vars: vars.len(),
+            classes: classes.len(),
+            bound,
+            max_chain,
+        }
+    }
+
+    /// Dump the union-find forest backing the table, for debugging
+    ///
+    /// Each variable is paired with None if it is the root of its tree, or
+    /// the variable it was linked under otherwise. These are the links as
+    /// they were made when classes were merged, before the path compression
+    /// done by [`probe`](Unifier::probe) and friends, so they show how each
+    /// representative was chosen
+    #[must_use]
+    pub fn dump_forest(&mut self) -> Vec<(Var, Option<Var>)> {
+        let parents = self.links.iter().copied().collect::<HashMap<_, _>>();
+        self.get_vars()
+            .into_iter()
+            .map(|var| (var, parents.get(&var).copied()))
+            .collect()
+    }
+
+    /// Unify each pair of variables, e.g. to restore a partition produced by
+    /// [`Table::export_partition`]
+    ///
+    /// Any variables mentioned which haven't been created in this table yet
+    /// are created first, as if by [`Table::var`]
+    ///
+    /// # Panics
+    /// If the table was built with a [variable
+    /// limit](TableBuilder::var_limit) and creating the variables reaches it
+    pub fn import_partition(
+        &mut self,
+        pairs: &[(Var, Var)],
+    ) -> Result<(), T::Error> {
+        let offset = self.options.var_offset;
+        for &(var, root) in pairs {
+            let (var, root) = (var.annotate(offset), root.annotate(offset));
+            let needed = var.index().max(root.index()) as usize + 1;
+            while self.table().len() < needed {
+                let _ = self.var();
+            }
+            self.link(var, root)?;
+        }
+        Ok(())
+    }
+
+    /// Check that the representatives in the underlying table are consistent
+    /// and that every variable probes to the same value as its representative
+    #[cfg(test)]
+    pub(crate) fn debug_check_invariants(&mut self)
+    where
+        T: PartialEq,
+    {
+        for index in 0..self.table().len() {
+            let var = TypedVar::<T>::from_index(
+                u32::try_from(index).expect("ena keys are u32"),
+            );
+            let root = self.table().find(var);
+            assert_eq!(
+                root,
+                self.table().find(root),
+                "Representative of {var:?} is not a root"
+            );
+            let value = self.table().probe_value(var);
+            assert_eq!(
+                Some(&value),
+                self.table().try_probe_value(root),
+                "{var:?} disagrees with its representative {root:?}"
+            );
+        }
     }
 
     /// Perform unification
-    pub fn unify(mut self) -> Result<HashMap<Var, ValueOrVar<T>>, T::Error> {
+    pub fn unify(self) -> Result<HashMap<Var, ValueOrVar<T>>, T::Error> {
+        self.unify_spanned()
+            .map_err(|SpannedError { error, .. }| error)
+    }
+
+    /// Perform unification, also returning the variables which weren't bound
+    /// to a value
+    ///
+    /// The set holds the representative of each variable which resolved to a
+    /// variable rather than a value, i.e. every [`ValueOrVar::Var`] in the
+    /// map. Variables nested inside values aren't included
+    pub fn unify_with_unbound(self) -> Result<WithUnbound<T>, T::Error> {
+        let result = self.unify()?;
+        let unbound = result
+            .values()
+            .filter_map(|value| match value {
+                ValueOrVar::Value(_) => None,
+                ValueOrVar::Var(var) => Some(*var),
+            })
+            .collect();
+        Ok((result, unbound))
+    }
+
+    /// Perform unification, applying the constraints in the given order
+    ///
+    /// The order can change which variable ends up as the representative of
+    /// each class and which constraint reports a conflict. [`unify`]
+    /// uses [`ConstraintOrder::Fifo`]. Spans stay with their constraints
+    ///
+    /// [`unify`]: Table::unify
+    pub fn unify_ordered(
+        mut self,
+        order: ConstraintOrder<T>,
+    ) -> Result<HashMap<Var, ValueOrVar<T>>, T::Error> {
+        let mut constraints = mem::take(&mut self.constraints)
+            .into_iter()
+            .zip(mem::take(&mut self.spans))
+            .collect::<Vec<_>>();
+        match order {
+            ConstraintOrder::Fifo => (),
+            ConstraintOrder::Lifo => constraints.reverse(),
+            ConstraintOrder::SortedBy(compare) => {
+                constraints.sort_by(|((left, _), _), ((right, _), _)| {
+                    compare(left, right)
+                });
+            }
+        }
+        (self.constraints, self.spans) = constraints.into_iter().unzip();
+        self.unify()
+    }
+
+    /// Perform unification, reporting the span of the constraint which failed
+    /// along with the error, see [`Table::constraint_spanned`]
+    pub fn unify_spanned(
+        self,
+    ) -> Result<HashMap<Var, ValueOrVar<T>>, SpannedError<T::Error, S>> {
+        self.unify_recorded().0
+    }
+
+    // Table::unify_spanned, also returning the steps recorded along the way
+    /// Perform unification, unifying the constraints in `borrowed` after the
+    /// table's own
+    ///
+    /// Borrowed values are only cloned as their constraint is unified, so
+    /// nothing is cloned for constraints which aren't reached because an
+    /// earlier one failed
+    pub fn unify_cow(
+        self,
+        borrowed: CowConstraints<'_, T>,
+    ) -> Result<HashMap<Var, ValueOrVar<T>>, T::Error> {
+        let (vars, constraints, deferred, mut unifier) = self.into_unifier();
+        // Spans aren't reported so every constraint can use the same index
+        let mut pending = Pending::new();
+        for (deferred, _) in deferred {
+            pending.push(deferred, 0);
+        }
+        let owned = constraints
+            .into_iter()
+            .map(|((constraint, _), _)| constraint);
+        let borrowed = borrowed
+            .0
+            .into_iter()
+            .map(|(left, right)| (left.into_owned(), right.into_owned()));
+        pending
+            .solve(&mut unifier, owned.chain(borrowed))
+            .map_err(|(error, _)| error)?;
+        Ok(unifier.probe_all(vars))
+    }
+
+    fn unify_recorded(self) -> (UnifySpanned<T, S>, Vec<Step<T>>) {
+        let (vars, constraints, deferred, mut unifier) = self.into_unifier();
+        // Deferred constraints refer to their spans by index so constraints
+        // deferred while running another can share its span
+        let (constraints, mut spans): (Vec<_>, Vec<_>) = constraints
+            .into_iter()
+            .map(|((constraint, _), span)| (constraint, span))
+            .unzip();
+        let mut pending = Pending::new();
+        for (deferred, span) in deferred {
+            pending.push(deferred, spans.len());
+            spans.push(span);
+        }
+        let result = pending
+            .solve(&mut unifier, constraints)
+            .map(|()| unifier.probe_all(vars))
+            .map_err(|(error, index)| SpannedError {
+                error,
+                span: spans.swap_remove(index),
+            });
+        (result, unifier.0.history.unwrap_or_default())
+    }
+
+    /// Perform unification, giving up after `max_steps` calls to
+    /// [`Unify::unify`], e.g. to bound the work done on pathological input
+    /// without a timer
+    ///
+    /// Every call is counted, including the ones made through
+    /// [`Unifier::unify`] while unifying nested values and from
+    /// [deferred](Table::defer) constraints. [`unify`](Table::unify) is the
+    /// unlimited case. If the budget runs out the error carries the values
+    /// found so far, the constraint which ran out may only be partly applied
+    pub fn unify_with_budget(
+        self,
+        max_steps: usize,
+    ) -> Result<HashMap<Var, ValueOrVar<T>>, BudgetError<T, T::Error>> {
+        let (vars, constraints, deferred, mut unifier) = self.into_unifier();
+        unifier.0.budget = Some(Budget {
+            remaining: max_steps,
+            exceeded: false,
+        });
+        // Spans aren't reported so every constraint can use the same index
+        let mut pending = Pending::new();
+        for (deferred, _) in deferred {
+            pending.push(deferred, 0);
+        }
+        // Once the budget runs out calls are skipped, so any error is a
+        // consequence of that rather than a real conflict
+        let check = |unifier: &mut Unifier<T>, result| match result {
+            _ if unifier.over_budget() => {
+                Err(BudgetError::Exceeded(BudgetExceeded {
+                    max_steps,
+                    partial: unifier.probe_all(vars.clone()),
+                }))
+            }
+            Ok(()) => Ok(()),
+            Err((error, _)) => Err(BudgetError::Unify(error)),
+        };
+        let result = pending.run(&mut unifier);
+        check(&mut unifier, result)?;
+        for ((constraint, _), _) in constraints {
+            let result = pending.step(&mut unifier, 0, constraint);
+            check(&mut unifier, result)?;
+        }
+        let result = pending.finish();
+        check(&mut unifier, result)?;
+        Ok(unifier.probe_all(vars))
+    }
+
+    /// Perform unification, combining concrete values with [`Unify::join`]
+    /// instead of [`Unify::merge`] when they meet
+    ///
+    /// This suits languages with subtyping, where two values only need a
+    /// common supertype rather than to be equal
+    pub fn unify_subtyping(
+        mut self,
+    ) -> Result<HashMap<Var, ValueOrVar<T>>, T::Error> {
+        let offset = self.options.var_offset;
+        self.subtyping = true;
+        for var in self.get_vars() {
+            // There is no value to merge so this can't fail
+            let Ok(()) = self
+                .table()
+                .unify_var_value(var.annotate(offset), Value::subtyping())
+            else {
+                unreachable!("Unifying with no value can't fail")
+            };
+        }
+        self.unify()
+    }
+
+    /// Perform unification, dropping constraints until the rest are
+    /// consistent
+    ///
+    /// Constraints are applied from the highest
+    /// [weight](Table::constraint_weighted) to the lowest, any constraint which
+    /// conflicts with those already applied is dropped. This is a greedy
+    /// approximation, it won't always drop the lightest possible set of
+    /// constraints. The dropped constraints are returned along with the
+    /// solution
+    ///
+    /// [Deferred](Table::defer) constraints run as soon as they are ready,
+    /// whatever their weight. One which fails is dropped too, as is one whose
+    /// variable never resolves. Neither is included in the returned list
+    pub fn unify_min_conflict(
+        self,
+    ) -> (HashMap<Var, ValueOrVar<T>>, Vec<Constraint<T>>) {
+        let (vars, mut constraints, deferred, mut unifier) =
+            self.into_unifier();
+        let mut pending = Pending::new();
+        for (deferred, _) in deferred {
+            pending.push(deferred, 0);
+        }
+        pending.run_lenient(&mut unifier, drop);
+        // Stable so constraints of equal weight are applied in the order they
+        // were added
+        constraints.sort_by_key(|&((_, weight), _)| Reverse(weight));
+        let mut dropped = Vec::new();
+        for (((left, right), _), _) in constraints {
+            let snapshot = unifier.0.snapshot();
+            if unifier.dispatch(left.clone(), right.clone()).is_ok() {
+                unifier.0.commit(snapshot);
+                pending.run_lenient(&mut unifier, drop);
+            } else {
+                unifier.0.rollback_to(snapshot);
+                dropped.push((left, right));
+            }
+        }
+        (unifier.probe_all(vars), dropped)
+    }
+
+    /// Perform unification, continuing past failing constraints
+    ///
+    /// Any changes made by a failing constraint are rolled back and its error
+    /// is collected. If the table was built with a [recovery
+    /// value](TableBuilder::recovery) then any unbound variables on either
+    /// side of the failing constraint are resolved to it so they don't
+    /// surface as spurious unbound variables
+    ///
+    /// [Deferred](Table::defer) constraints run as soon as they are ready. The
+    /// changes made by one which fails are rolled back and its error is
+    /// collected, as is an [`UnresolvedVariableError`] for each one whose
+    /// variable never resolves
+    pub fn unify_with_recovery(
+        mut self,
+    ) -> (HashMap<Var, ValueOrVar<T>>, Vec<T::Error>) {
+        let recovery = self.options.recovery.take();
+        let (vars, constraints, deferred, mut unifier) = self.into_unifier();
+        let mut pending = Pending::new();
+        for (deferred, _) in deferred {
+            pending.push(deferred, 0);
+        }
+        let mut errors = Vec::new();
+        pending.run_lenient(&mut unifier, |error| errors.push(error));
+        for (((left, right), _), _) in constraints {
+            let sides = [&left, &right].map(|side| match side {
+                ValueOrVar::Value(_) => None,
+                ValueOrVar::Var(var) => Some(*var),
+            });
+            let snapshot = unifier.0.snapshot();
+            match unifier.dispatch(left, right) {
+                Ok(()) => {
+                    unifier.0.commit(snapshot);
+                    pending
+                        .run_lenient(&mut unifier, |error| errors.push(error));
+                }
+                Err(e) => {
+                    unifier.0.rollback_to(snapshot);
+                    errors.push(e);
+                    let Some(recovery) = &recovery else {
+                        continue;
+                    };
+                    for var in sides.into_iter().flatten() {
+                        if let ValueOrVar::Var(var) = unifier.probe(var) {
+                            // The variable is unbound so this can't fail
+                            let _ =
+                                unifier.unify_var_value(var, recovery.clone());
+                        }
+                    }
+                }
+            }
+        }
+        errors.extend(pending.unresolved().map(|(error, _)| error));
+        (unifier.probe_all(vars), errors)
+    }
+
+    // The deferred constraints are handed back so every way of unifying has
+    // to deal with them
+    fn into_unifier(self) -> IntoUnifier<T, S> {
         let vars = self.get_vars();
-        let constraints = mem::take(&mut self.constraints);
-        let mut unifier = Unifier(self);
-        for (left, right) in constraints {
-            T::unify(left, right, &mut unifier)?;
+        let Self {
+            unification_table,
+            clean_snapshot,
+            constraints,
+            spans,
+            deferred,
+            names,
+            leveled,
+            subtyping,
+            history,
+            links,
+            budget,
+            options,
+        } = self;
+        let keep = options.dedup.map(|dedup| dedup(&constraints));
+        let mut constraints =
+            constraints.into_iter().zip(spans).collect::<Vec<_>>();
+        if let Some(keep) = keep {
+            // retain visits the elements in order so this lines up with the
+            // flags
+            let mut keep = keep.into_iter();
+            constraints
+                .retain(|_| keep.next().expect("One flag per constraint"));
         }
-        let mut result = HashMap::new();
-        for var in vars {
-            let value = unifier.probe(var);
-            let _ = result.insert(var, value);
+        // The unifier doesn't need the spans
+        let table = Table {
+            unification_table,
+            clean_snapshot,
+            constraints: Vec::new(),
+            spans: Vec::new(),
+            deferred: Vec::new(),
+            names,
+            leveled,
+            subtyping,
+            history,
+            links,
+            budget,
+            options,
+        };
+        (vars, constraints, deferred, Unifier(table))
+    }
+
+    fn table(&mut self) -> &mut InPlaceUnificationTable<TypedVar<T>> {
+        self.unification_table.get_mut()
+    }
+
+    // Unify two variables in the underlying table, recording the link it
+    // makes for Table::dump_forest
+    fn link(
+        &mut self,
+        left: TypedVar<T>,
+        right: TypedVar<T>,
+    ) -> Result<(), T::Error> {
+        let offset = self.options.var_offset;
+        let table = self.table();
+        let (left, right) = (table.find(left), table.find(right));
+        table.unify_var_var(left, right)?;
+        if left != right {
+            let root = table.find(left);
+            let child = if root == left { right } else { left };
+            self.links.push((child.erase(offset), root.erase(offset)));
         }
-        Ok(result)
+        Ok(())
     }
 
     fn get_vars(&self) -> Vec<Var> {
+        let offset = self.options.var_offset;
         let Range { start, end } = self
             .unification_table
+            .borrow()
             .vars_since_snapshot(&self.clean_snapshot);
-        let Var(start) = start.erase();
-        let Var(end) = end.erase();
+        let Var(start) = start.erase(offset);
+        let Var(end) = end.erase(offset);
         let mut result = Vec::with_capacity((end - start) as usize);
         for i in start..end {
             result.push(Var(i));
@@ -113,6 +1142,61 @@ impl<T: Unify> Table<T> {
 pub struct Unifier<T: Unify>(Table<T>);
 
 impl<T: Unify> Unifier<T> {
+    fn dispatch(
+        &mut self,
+        left: ValueOrVar<T>,
+        right: ValueOrVar<T>,
+    ) -> Result<(), T::Error> {
+        if let Some(trace) = &self.0.options.trace {
+            // Only poisoned if an earlier call panicked, which can't have left
+            // the table inconsistent
+            let mut trace =
+                trace.lock().unwrap_or_else(PoisonError::into_inner);
+            trace(&left, &right);
+        }
+        self.record(|| Step::Constraint {
+            left: left.clone(),
+            right: right.clone(),
+        });
+        self.unify(left, right)
+    }
+
+    /// Normalize both sides and pass them to [`Unify::unify`], e.g. to unify
+    /// the children of two values
+    ///
+    /// Prefer this to calling [`Unify::unify`] directly so the call counts
+    /// towards the budget of [`Table::unify_with_budget`]
+    pub fn unify(
+        &mut self,
+        left: ValueOrVar<T>,
+        right: ValueOrVar<T>,
+    ) -> Result<(), T::Error> {
+        if let Some(budget) = &mut self.0.budget {
+            if budget.remaining == 0 {
+                budget.exceeded = true;
+                return Ok(());
+            }
+            budget.remaining -= 1;
+        }
+        let left = canonicalize(T::normalize(left, self));
+        let right = canonicalize(T::normalize(right, self));
+        T::unify(left, right, self)
+    }
+
+    // True if a call to Unifier::unify was skipped because the budget ran out
+    fn over_budget(&self) -> bool {
+        self.0.budget.as_ref().is_some_and(|budget| budget.exceeded)
+    }
+
+    fn probe_all(&mut self, vars: Vec<Var>) -> HashMap<Var, ValueOrVar<T>> {
+        let mut result = HashMap::new();
+        for var in vars {
+            let value = self.probe(var);
+            let _ = result.insert(var, value);
+        }
+        result
+    }
+
     /// Look up the current value of a unification variable
     ///
     /// If the variable has been unified with a concrete value already then that
@@ -122,13 +1206,101 @@ impl<T: Unify> Unifier<T> {
     /// representative variable is returned, this may not be the same as the one
     /// passed in
     pub fn probe(&mut self, var: Var) -> ValueOrVar<T> {
-        let var = var.annotate();
-        match self.0.unification_table.probe_value(var) {
-            Some(Value(value)) => ValueOrVar::Value(value),
-            None => ValueOrVar::Var(self.0.unification_table.find(var).erase()),
+        let offset = self.0.options.var_offset;
+        let var = var.annotate(offset);
+        match self.0.table().probe_value(var).value {
+            Some(value) => ValueOrVar::Value(value),
+            None => ValueOrVar::Var(self.0.table().find(var).erase(offset)),
+        }
+    }
+
+    /// True if `var` has been unified with a concrete value, i.e.
+    /// [`probe`](Unifier::probe) would return a [`ValueOrVar::Value`]
+    pub fn is_bound(&mut self, var: Var) -> bool {
+        let offset = self.0.options.var_offset;
+        self.0
+            .table()
+            .probe_value(var.annotate(offset))
+            .value
+            .is_some()
+    }
+
+    /// Resolve `value` to a concrete value or a representative variable if it
+    /// is a variable, see [`probe`](Unifier::probe)
+    ///
+    /// Variables nested inside a concrete value are left alone, this is the
+    /// top level part of [`Unify::normalize`]
+    pub fn normalize(&mut self, value: ValueOrVar<T>) -> ValueOrVar<T> {
+        match value {
+            ValueOrVar::Value(value) => ValueOrVar::Value(value),
+            ValueOrVar::Var(var) => self.probe(var),
         }
     }
 
+    /// Check whether `var` occurs anywhere inside `value`, following any
+    /// variables which have already been unified with a value
+    ///
+    /// Unifying a variable with a value containing it would produce an
+    /// infinite value. Walks the value using [`Unify::children`]
+    pub fn occurs(&mut self, var: Var, value: &T) -> bool {
+        let offset = self.0.options.var_offset;
+        let root = self.0.table().find(var.annotate::<T>(offset));
+        for child in value.children() {
+            let found = match child {
+                ValueOrVar::Value(child) => self.occurs(var, child),
+                ValueOrVar::Var(child) => match self.probe(*child) {
+                    ValueOrVar::Value(child) => self.occurs(var, &child),
+                    ValueOrVar::Var(child) => {
+                        child.annotate::<T>(offset) == root
+                    }
+                },
+            };
+            if found {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Whether this is [`Table::unify_subtyping`], in which case
+    /// [`Unify::unify`] should compare concrete values with [`Unify::join`]
+    /// rather than [`Unify::merge`]
+    #[must_use]
+    pub fn subtyping(&self) -> bool {
+        self.0.subtyping
+    }
+
+    /// The representative of the variable's equivalence class, whether or not
+    /// the class has been unified with a value
+    ///
+    /// Two variables have been unified exactly when they have the same
+    /// representative
+    pub fn representative(&mut self, var: Var) -> Var {
+        let offset = self.0.options.var_offset;
+        self.0.table().find(var.annotate::<T>(offset)).erase(offset)
+    }
+
+    /// Like [`probe`](Unifier::probe) but only needs a shared reference
+    ///
+    /// The underlying table still shortens the path from `var` to its
+    /// representative, through interior mutability, so this costs an extra
+    /// runtime borrow check over [`probe`](Unifier::probe)
+    #[must_use]
+    pub fn probe_shallow(&self, var: Var) -> ValueOrVar<T> {
+        let offset = self.0.options.var_offset;
+        let var = var.annotate(offset);
+        let mut table = self.0.unification_table.borrow_mut();
+        match table.probe_value(var).value {
+            Some(value) => ValueOrVar::Value(value),
+            None => ValueOrVar::Var(table.find(var).erase(offset)),
+        }
+    }
+
+    /// Format a variable for display, see [`Table::fmt_var`]
+    pub fn fmt_var(&mut self, var: Var) -> String {
+        self.0.fmt_var(var)
+    }
+
     /// Unify two variables
     ///
     /// Unifying two variables has three possible outcomes
@@ -145,9 +1317,47 @@ impl<T: Unify> Unifier<T> {
         left: Var,
         right: Var,
     ) -> Result<(), T::Error> {
-        self.0
-            .unification_table
-            .unify_var_var(left.annotate(), right.annotate())
+        let offset = self.0.options.var_offset;
+        let values = self
+            .recording()
+            .then(|| (self.probe(left), self.probe(right)));
+        self.0.link(left.annotate(offset), right.annotate(offset))?;
+        self.record(|| Step::VarVar { left, right });
+        if let Some((ValueOrVar::Value(left_value), ValueOrVar::Value(right))) =
+            values
+        {
+            self.record_merge(left, left_value, right);
+        }
+        Ok(())
+    }
+
+    /// Measure how deeply nested a value is
+    ///
+    /// Variables are resolved to their current value first, a variable which
+    /// hasn't been bound counts as depth 0 and a value with no children as
+    /// depth 1. `children` lists the values nested directly inside a value
+    pub fn value_depth(
+        &mut self,
+        value: ValueOrVar<T>,
+        children: impl Fn(&T) -> Vec<ValueOrVar<T>>,
+    ) -> usize {
+        self.depth(value, &children)
+    }
+
+    fn depth(
+        &mut self,
+        value: ValueOrVar<T>,
+        children: &impl Fn(&T) -> Vec<ValueOrVar<T>>,
+    ) -> usize {
+        let ValueOrVar::Value(value) = T::normalize(value, self) else {
+            return 0;
+        };
+        children(&value)
+            .into_iter()
+            .map(|child| self.depth(child, children))
+            .max()
+            .unwrap_or(0)
+            + 1
     }
 
     /// Unify a variable with a concrete value
@@ -164,14 +1374,141 @@ impl<T: Unify> Unifier<T> {
         var: Var,
         typ: T,
     ) -> Result<(), T::Error> {
+        self.check_value(var, &typ)?;
+        self.bind(var, typ)
+    }
+
+    /// Unify a variable with a borrowed concrete value, see
+    /// [`Unifier::unify_var_value`]
+    ///
+    /// [`Unify::merge`] only ever sees the value by reference but the table
+    /// stores values by value, so this clones `typ` once
+    pub fn unify_var_value_ref(
+        &mut self,
+        var: Var,
+        typ: &T,
+    ) -> Result<(), T::Error> {
+        self.check_value(var, typ)?;
+        self.bind(var, typ.clone())
+    }
+
+    // Once levels are in use the variables inside the value are lowered to the
+    // variable's level, they can only be generalized where it can. Unifying
+    // with an unbound value can't fail
+    fn bind(&mut self, var: Var, typ: T) -> Result<(), T::Error> {
+        let offset = self.0.options.var_offset;
+        let lowered = if self.0.leveled {
+            let mut vars = Vec::new();
+            free_vars(&self.0.zonk(ValueOrVar::Value(typ.clone())), &mut vars);
+            vars
+        } else {
+            Vec::new()
+        };
+        let level = self.0.level(var);
+        let recorded = self.recording().then(|| (self.probe(var), typ.clone()));
         self.0
-            .unification_table
-            .unify_var_value(var.annotate(), Some(Value(typ)))
+            .table()
+            .unify_var_value(var.annotate(offset), Value::bound(typ))?;
+        if let Some((previous, value)) = recorded {
+            self.record(|| Step::VarValue {
+                var,
+                value: value.clone(),
+            });
+            if let ValueOrVar::Value(previous) = previous {
+                self.record_merge(var, previous, value);
+            }
+        }
+        for inner in lowered {
+            self.0.table().unify_var_value(
+                inner.annotate(offset),
+                Value::unbound(level),
+            )?;
+        }
+        Ok(())
+    }
+
+    // The checks configured by TableBuilder::depth_limit and
+    // TableBuilder::occurs_check
+    fn check_value(&mut self, var: Var, typ: &T) -> Result<(), T::Error> {
+        if let Some(&DepthLimit {
+            limit,
+            children,
+            error,
+        }) = self.0.options.depth_limit.as_ref()
+        {
+            let depth =
+                self.value_depth(ValueOrVar::Value(typ.clone()), children);
+            if depth > limit {
+                return Err(error(DepthLimitError { depth, limit }));
+            }
+        }
+        if let Some(error) = self.0.options.occurs_check
+            && self.occurs(var, typ)
+        {
+            return Err(error(OccursError(var)));
+        }
+        Ok(())
+    }
+}
+
+/// Resolve each value with [`ValueOrVar::resolve_mono`] in order, stopping
+/// at the first unresolved variable
+#[expect(clippy::implicit_hasher)]
+pub fn resolve_mono_all<T: Clone>(
+    values: impl IntoIterator<Item = ValueOrVar<T>>,
+    types: &HashMap<Var, ValueOrVar<T>>,
+    walk: impl Fn(
+        T,
+        &HashMap<Var, ValueOrVar<T>>,
+    ) -> Result<T, UnresolvedVariableError>,
+) -> Result<Vec<T>, UnresolvedVariableError> {
+    values
+        .into_iter()
+        .map(|value| value.resolve_mono(types, &walk))
+        .collect()
+}
+
+/// Format the map returned by [`Table::unify`] for debugging, one variable per
+/// line in ascending order
+///
+/// Chains of variables are followed to the value or variable at the end, and
+/// values are formatted with [`Unify::fmt_pretty`] at one level of
+/// indentation
+#[expect(clippy::implicit_hasher)]
+#[must_use]
+pub fn pretty<T: Unify + Display>(map: &HashMap<Var, ValueOrVar<T>>) -> String {
+    let mut vars = map.keys().copied().collect::<Vec<_>>();
+    vars.sort_by_key(|&Var(var)| var);
+    let mut result = String::new();
+    for var in vars {
+        let mut end = var;
+        let mut seen = HashSet::from([var]);
+        let value = loop {
+            match map.get(&end) {
+                Some(ValueOrVar::Value(value)) => break value.fmt_pretty(1),
+                Some(&ValueOrVar::Var(next)) if seen.insert(next) => end = next,
+                _ => break end.to_string(),
+            }
+        };
+        let _ = writeln!(result, "{var} = {value}");
+    }
+    result
+}
+
+// Only clones if Unify::canonicalize changed the value
+fn canonicalize<T: Unify>(value: ValueOrVar<T>) -> ValueOrVar<T> {
+    match value {
+        ValueOrVar::Value(value) => match value.canonicalize() {
+            Cow::Borrowed(_) => ValueOrVar::Value(value),
+            Cow::Owned(canonical) => ValueOrVar::Value(canonical),
+        },
+        var @ ValueOrVar::Var(_) => var,
     }
 }
 
 /// Wrapper for a concrete value or a unification variable
 #[value_type]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ValueOrVar<T> {
     #[allow(missing_docs)]
     Value(T),
@@ -179,6 +1516,197 @@ pub enum ValueOrVar<T> {
     Var(Var),
 }
 
+/// Wrapper for a possibly borrowed concrete value or a unification variable,
+/// see [`CowConstraints`]
+#[derive(Debug, Clone)]
+pub enum CowValueOrVar<'a, T: Clone> {
+    #[allow(missing_docs)]
+    Value(Cow<'a, T>),
+    #[allow(missing_docs)]
+    Var(Var),
+}
+
+impl<T: Clone> CowValueOrVar<'_, T> {
+    /// Convert to a [`ValueOrVar`], cloning the value if it is borrowed
+    #[must_use]
+    pub fn into_owned(self) -> ValueOrVar<T> {
+        match self {
+            CowValueOrVar::Value(value) => {
+                ValueOrVar::Value(value.into_owned())
+            }
+            CowValueOrVar::Var(var) => ValueOrVar::Var(var),
+        }
+    }
+}
+
+impl<'a, T: Clone> From<&'a T> for CowValueOrVar<'a, T> {
+    fn from(value: &'a T) -> Self {
+        CowValueOrVar::Value(Cow::Borrowed(value))
+    }
+}
+
+impl<T: Clone> From<Var> for CowValueOrVar<'_, T> {
+    fn from(var: Var) -> Self {
+        CowValueOrVar::Var(var)
+    }
+}
+
+impl<T: Clone> From<ValueOrVar<T>> for CowValueOrVar<'_, T> {
+    fn from(value: ValueOrVar<T>) -> Self {
+        match value {
+            ValueOrVar::Value(value) => CowValueOrVar::Value(Cow::Owned(value)),
+            ValueOrVar::Var(var) => CowValueOrVar::Var(var),
+        }
+    }
+}
+
+/// Constraints built from possibly borrowed values, see [`Table::unify_cow`]
+///
+/// Keeping them apart from the table means it doesn't have to outlive the
+/// borrow, so borrowed values can be left alone until they are unified
+#[derive(Debug)]
+pub struct CowConstraints<'a, T: Clone>(Vec<CowConstraint<'a, T>>);
+
+type CowConstraint<'a, T> = (CowValueOrVar<'a, T>, CowValueOrVar<'a, T>);
+
+impl<T: Clone> Default for CowConstraints<'_, T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<'a, T: Clone> CowConstraints<'a, T> {
+    /// Constructor
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a new constraint, owned values are moved in and borrowed values
+    /// are kept borrowed
+    pub fn constraint(
+        &mut self,
+        left: CowValueOrVar<'a, T>,
+        right: CowValueOrVar<'a, T>,
+    ) {
+        self.0.push((left, right));
+    }
+}
+
+/// Statistics about the variables in a table, see [`Table::stats`]
+#[value_type(Copy)]
+pub struct UnifyStats {
+    /// The number of variables
+    pub vars: usize,
+    /// The number of equivalence classes, i.e. distinct representatives
+    pub classes: usize,
+    /// The number of variables whose class has a concrete value
+    pub bound: usize,
+    /// The most links between a variable and its representative, as they were
+    /// made when classes were merged. Path compression only ever shortens the
+    /// chains the underlying table walks, so no probe takes longer than this
+    pub max_chain: usize,
+}
+
+// The number of links between `var` and its representative, following the
+// child to parent links recorded by Table::link
+fn depth(
+    var: Var,
+    parents: &HashMap<Var, Var>,
+    depths: &mut HashMap<Var, usize>,
+) -> usize {
+    if let Some(&depth) = depths.get(&var) {
+        return depth;
+    }
+    let depth = match parents.get(&var) {
+        Some(&parent) => depth(parent, parents, depths) + 1,
+        None => 0,
+    };
+    let _ = depths.insert(var, depth);
+    depth
+}
+
+/// The order [`Table::unify_ordered`] applies constraints in
+#[derive(Debug)]
+pub enum ConstraintOrder<T> {
+    /// The order they were added, as [`Table::unify`] does
+    Fifo,
+    /// The reverse of the order they were added
+    Lifo,
+    /// Sorted by the comparison function, constraints which compare equal
+    /// keep the order they were added
+    SortedBy(fn(&Constraint<T>, &Constraint<T>) -> Ordering),
+}
+
+impl<T> Clone for ConstraintOrder<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ConstraintOrder<T> {}
+
+/// Error returned from [`Table::unify_spanned`], pairs the error with the span
+/// of the constraint which raised it
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{error}")]
+pub struct SpannedError<E, S> {
+    /// The error raised by [`Unify::unify`]
+    pub error: E,
+    /// The span given to [`Table::constraint_spanned`]
+    pub span: S,
+}
+
+/// Error returned from [`Table::unify_with_budget`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BudgetError<T, E> {
+    /// A constraint failed to unify
+    #[error("{0}")]
+    Unify(E),
+    /// The budget ran out before every constraint was unified
+    #[error("{0}")]
+    Exceeded(BudgetExceeded<T>),
+}
+
+/// Error returned from [`Table::unify_with_budget`] if unification needs more
+/// steps than the budget allows
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Unification budget of {max_steps} steps exceeded")]
+pub struct BudgetExceeded<T> {
+    /// The budget
+    pub max_steps: usize,
+    /// The value of every variable after the steps which were taken, in the
+    /// same form as [`Table::unify`]
+    pub partial: HashMap<Var, ValueOrVar<T>>,
+}
+
+/// Error returned from [`Table::try_var`] if the table has already created
+/// as many variables as its [limit](TableBuilder::var_limit) allows
+#[value_type(Copy)]
+#[derive(thiserror::Error)]
+#[error("Variable limit of {0} exceeded")]
+pub struct VarLimitError(pub u32);
+
+/// Error produced by [`Unifier::unify_var_value`] if a table was built with
+/// a [depth limit](TableBuilder::depth_limit) and the value is nested too deeply
+#[value_type(Copy)]
+#[derive(thiserror::Error)]
+#[error("Value nested {depth} deep exceeds the depth limit of {limit}")]
+pub struct DepthLimitError {
+    /// Depth of the rejected value
+    pub depth: usize,
+    /// The configured limit
+    pub limit: usize,
+}
+
+/// Error produced by [`Unifier::unify_var_value`] if a table was built with
+/// the [occurs check](TableBuilder::occurs_check) enabled and the variable
+/// occurs in the value
+#[value_type(Copy)]
+#[derive(thiserror::Error)]
+#[error("Unification variable {0} occurs in the value it is unified with")]
+pub struct OccursError(pub Var);
+
 /// Error returned from [`ValueOrVar::resolve_mono`] if the value cannot be
 /// resolved to a monomorphic type
 #[value_type(Copy)]
@@ -186,6 +1714,63 @@ pub enum ValueOrVar<T> {
 #[error("Unresolved unification variable {0}")]
 pub struct UnresolvedVariableError(Var);
 
+impl<T> ValueOrVar<T> {
+    /// The variable, if this is a variable
+    #[must_use]
+    pub fn as_var(&self) -> Option<Var> {
+        match self {
+            ValueOrVar::Value(_) => None,
+            ValueOrVar::Var(var) => Some(*var),
+        }
+    }
+
+    /// The value, if this is a value
+    #[must_use]
+    pub fn as_value(&self) -> Option<&T> {
+        match self {
+            ValueOrVar::Value(value) => Some(value),
+            ValueOrVar::Var(_) => None,
+        }
+    }
+
+    /// Take the value, if this is a value
+    #[must_use]
+    pub fn into_value(self) -> Option<T> {
+        match self {
+            ValueOrVar::Value(value) => Some(value),
+            ValueOrVar::Var(_) => None,
+        }
+    }
+
+    /// Transform the value, variables are passed through unchanged
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> ValueOrVar<U> {
+        match self {
+            ValueOrVar::Value(value) => ValueOrVar::Value(f(value)),
+            ValueOrVar::Var(var) => ValueOrVar::Var(var),
+        }
+    }
+
+    /// Like [`map`](ValueOrVar::map) but borrows the value
+    pub fn map_ref<U>(&self, f: impl FnOnce(&T) -> U) -> ValueOrVar<U> {
+        match self {
+            ValueOrVar::Value(value) => ValueOrVar::Value(f(value)),
+            ValueOrVar::Var(var) => ValueOrVar::Var(*var),
+        }
+    }
+
+    /// True if this is a variable
+    #[must_use]
+    pub fn is_var(&self) -> bool {
+        matches!(self, ValueOrVar::Var(_))
+    }
+
+    /// True if this is a value
+    #[must_use]
+    pub fn is_value(&self) -> bool {
+        matches!(self, ValueOrVar::Value(_))
+    }
+}
+
 impl<T: Clone> ValueOrVar<T> {
     /// Resolve a polymorphic value to it's canonical representation based on the
     /// map returned by [`Table::unify`]
@@ -206,6 +1791,16 @@ impl<T: Clone> ValueOrVar<T> {
         }
     }
 
+    /// Like [`resolve`](ValueOrVar::resolve) but walks the value with
+    /// [`Unify::substitute`]
+    #[must_use]
+    pub fn resolve_auto(self, table: &HashMap<Var, ValueOrVar<T>>) -> Self
+    where
+        T: Unify,
+    {
+        self.resolve(table, T::substitute)
+    }
+
     /// Resolve a polymorphic value to it's canonical monomorphic representation
     /// based on the type map returned by [`Table::unify`]
     pub fn resolve_mono(
@@ -0,0 +1,205 @@
+use std::{
+    collections::HashSet,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+use super::{DepthLimitError, OccursError, Table, Unify, ValueOrVar, Weighted};
+
+// Flags the constraints to keep, so their spans can be dropped alongside them
+type Dedup<T> = fn(&[Weighted<T>]) -> Vec<bool>;
+
+// Shared so forked tables report to the same callback
+type Trace<T> = Arc<Mutex<dyn FnMut(&ValueOrVar<T>, &ValueOrVar<T>) + Send>>;
+
+/// Configuration shared between [`TableBuilder`] and [`Table`]
+pub(crate) struct Options<T: Unify> {
+    pub(crate) trace: Option<Trace<T>>,
+    pub(crate) history: bool,
+    pub(crate) dedup: Option<Dedup<T>>,
+    pub(crate) var_limit: Option<u32>,
+    pub(crate) var_offset: u32,
+    pub(crate) recovery: Option<T>,
+    pub(crate) depth_limit: Option<DepthLimit<T>>,
+    pub(crate) occurs_check: Option<fn(OccursError) -> T::Error>,
+}
+
+impl<T: Unify> Default for Options<T> {
+    fn default() -> Self {
+        Self {
+            trace: None,
+            history: false,
+            dedup: None,
+            var_limit: None,
+            var_offset: 0,
+            recovery: None,
+            depth_limit: None,
+            occurs_check: None,
+        }
+    }
+}
+
+// Written out rather than derived, derive would require T::Error: Clone
+impl<T: Unify> Clone for Options<T> {
+    fn clone(&self) -> Self {
+        Self {
+            trace: self.trace.clone(),
+            history: self.history,
+            dedup: self.dedup,
+            var_limit: self.var_limit,
+            var_offset: self.var_offset,
+            recovery: self.recovery.clone(),
+            depth_limit: self.depth_limit.as_ref().map(
+                |&DepthLimit {
+                     limit,
+                     children,
+                     error,
+                 }| DepthLimit {
+                    limit,
+                    children,
+                    error,
+                },
+            ),
+            occurs_check: self.occurs_check,
+        }
+    }
+}
+
+/// See [`TableBuilder::depth_limit`]
+pub(crate) struct DepthLimit<T: Unify> {
+    pub(crate) limit: usize,
+    pub(crate) children: fn(&T) -> Vec<ValueOrVar<T>>,
+    pub(crate) error: fn(DepthLimitError) -> T::Error,
+}
+
+/// Builder for a configured [`Table`]
+///
+/// Obtained from [`Table::builder`], each method enables one option and
+/// [`build`](TableBuilder::build) produces the table
+#[expect(missing_debug_implementations)]
+pub struct TableBuilder<T: Unify>(Options<T>);
+
+impl<T: Unify> Default for TableBuilder<T> {
+    fn default() -> Self {
+        Self(Options::default())
+    }
+}
+
+impl<T: Unify> TableBuilder<T> {
+    /// Constructor
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `trace` with both sides of each constraint as it is passed to
+    /// [`Unify::unify`], before either side is normalized
+    ///
+    /// Tables [forked](Table::fork) from this one share the callback
+    #[must_use]
+    pub fn trace(
+        mut self,
+        trace: impl FnMut(&ValueOrVar<T>, &ValueOrVar<T>) + Send + 'static,
+    ) -> Self {
+        self.0.trace = Some(Arc::new(Mutex::new(trace)));
+        self
+    }
+
+    /// Record each step taken while unifying, see [`Table::history`] and
+    /// [`Table::unify_with_history`]
+    #[must_use]
+    pub fn history(mut self) -> Self {
+        self.0.history = true;
+        self
+    }
+
+    /// Limit the number of variables the table can create, see
+    /// [`Table::try_var`]
+    #[must_use]
+    pub fn var_limit(mut self, limit: u32) -> Self {
+        self.0.var_limit = Some(limit);
+        self
+    }
+
+    /// Number variables from `start` rather than zero, e.g. to keep them
+    /// apart from identifiers allocated elsewhere
+    ///
+    /// Every variable produced or returned by the table, including the keys of
+    /// the map returned by [`Table::unify`], uses the offset. The
+    /// [limit](TableBuilder::var_limit) still counts variables from zero
+    #[must_use]
+    pub fn var_offset(mut self, start: u32) -> Self {
+        self.0.var_offset = start;
+        self
+    }
+
+    /// Value used to resolve unbound variables involved in a failing
+    /// constraint, see [`Table::unify_with_recovery`]
+    #[must_use]
+    pub fn recovery(mut self, value: T) -> Self {
+        self.0.recovery = Some(value);
+        self
+    }
+
+    /// Reject values nested more than `limit` deep when they are bound to a
+    /// variable, see [`Unifier::value_depth`](super::Unifier::value_depth)
+    #[must_use]
+    pub fn depth_limit(
+        mut self,
+        limit: usize,
+        children: fn(&T) -> Vec<ValueOrVar<T>>,
+    ) -> Self
+    where
+        T::Error: From<DepthLimitError>,
+    {
+        self.0.depth_limit = Some(DepthLimit {
+            limit,
+            children,
+            error: T::Error::from,
+        });
+        self
+    }
+
+    /// Reject binding a variable to a value containing it, see
+    /// [`Unifier::occurs`](super::Unifier::occurs)
+    #[must_use]
+    pub fn occurs_check(mut self) -> Self
+    where
+        T::Error: From<OccursError>,
+    {
+        self.0.occurs_check = Some(T::Error::from);
+        self
+    }
+
+    /// Produce the configured table
+    #[must_use]
+    pub fn build(self) -> Table<T> {
+        Table::with_options(self.0)
+    }
+
+    /// Produce the configured table, tagging constraints with spans of type
+    /// `S`, see [`Table::constraint_spanned`]
+    #[must_use]
+    pub fn build_spanned<S>(self) -> Table<T, S> {
+        Table::with_options(self.0)
+    }
+}
+
+impl<T: Unify + Eq + Hash> TableBuilder<T> {
+    /// Drop structurally identical constraints before unification. The order
+    /// of the remaining constraints is preserved
+    #[must_use]
+    pub fn dedup(mut self) -> Self {
+        self.0.dedup = Some(dedup);
+        self
+    }
+}
+
+// Weights don't count, the first copy of a constraint keeps its weight
+pub(super) fn dedup<T: Eq + Hash>(constraints: &[Weighted<T>]) -> Vec<bool> {
+    let mut seen = HashSet::new();
+    constraints
+        .iter()
+        .map(|(constraint, _)| seen.insert(constraint))
+        .collect()
+}
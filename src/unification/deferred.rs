@@ -0,0 +1,200 @@
+//! Constraints which wait for a variable to be resolved, see [`Table::defer`]
+
+use std::sync::Arc;
+
+use super::{Constraint, Table, Unifier, Unify, UnresolvedVariableError, Var};
+
+// Shared with forks of the table, see Table::fork
+type Callback<T> = Arc<
+    dyn Fn(&mut Unifier<T>, &T) -> Result<(), <T as Unify>::Error>
+        + Send
+        + Sync,
+>;
+
+pub(super) struct Deferred<T: Unify> {
+    var: Var,
+    constraint: Callback<T>,
+    // Reports the constraint if var is never resolved
+    unresolved: fn(UnresolvedVariableError) -> T::Error,
+}
+
+impl<T: Unify> Clone for Deferred<T> {
+    fn clone(&self) -> Self {
+        Self {
+            var: self.var,
+            constraint: Arc::clone(&self.constraint),
+            unresolved: self.unresolved,
+        }
+    }
+}
+
+impl<T: Unify, S> Table<T, S> {
+    /// Add a constraint which runs once `var` has been unified with a value,
+    /// e.g. a field access which needs to know the type of the record
+    ///
+    /// `constraint` is passed the value and can unify further variables.
+    /// Every way of unifying the table, including
+    /// [`constraint_now`](Table::constraint_now), re-checks deferred
+    /// constraints after every constraint until none are ready to run. If
+    /// `var` never resolves unification fails with an
+    /// [`UnresolvedVariableError`], see
+    /// [`unify_min_conflict`](Table::unify_min_conflict) and
+    /// [`unify_with_recovery`](Table::unify_with_recovery) for how they treat
+    /// deferred constraints which fail or never run
+    ///
+    /// `constraint` is shared with any [forks](Table::fork) of the table so it
+    /// can't consume anything it captures. It has to be `Send` and `Sync` so
+    /// the table can still be moved to another thread
+    pub fn defer(
+        &mut self,
+        var: Var,
+        constraint: impl Fn(&mut Unifier<T>, &T) -> Result<(), T::Error>
+        + Send
+        + Sync
+        + 'static,
+    ) where
+        S: Default,
+        T::Error: From<UnresolvedVariableError>,
+    {
+        self.defer_spanned(var, S::default(), constraint);
+    }
+
+    /// Like [`defer`](Table::defer) but the constraint is tagged with a span,
+    /// see [`Table::constraint_spanned`]
+    pub fn defer_spanned(
+        &mut self,
+        var: Var,
+        span: S,
+        constraint: impl Fn(&mut Unifier<T>, &T) -> Result<(), T::Error>
+        + Send
+        + Sync
+        + 'static,
+    ) where
+        T::Error: From<UnresolvedVariableError>,
+    {
+        self.deferred.push((
+            Deferred {
+                var,
+                constraint: Arc::new(constraint),
+                unresolved: T::Error::from,
+            },
+            span,
+        ));
+    }
+}
+
+// Deferred constraints waiting to run while unifying, each paired with the
+// index of its span
+pub(super) struct Pending<T: Unify>(Vec<(Deferred<T>, usize)>);
+
+impl<T: Unify> Pending<T> {
+    pub(super) fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub(super) fn push(&mut self, deferred: Deferred<T>, span: usize) {
+        self.0.push((deferred, span));
+    }
+
+    // The deferred constraints which haven't run, in the order they were
+    // deferred
+    pub(super) fn into_deferred(self) -> impl Iterator<Item = Deferred<T>> {
+        self.0.into_iter().map(|(deferred, _)| deferred)
+    }
+
+    // Remove the first constraint which is ready to run, with the value of
+    // its variable
+    fn take_ready(
+        &mut self,
+        unifier: &mut Unifier<T>,
+    ) -> Option<(Deferred<T>, usize, T)> {
+        let (index, value) =
+            self.0
+                .iter()
+                .enumerate()
+                .find_map(|(index, (deferred, _))| {
+                    let value = unifier.probe(deferred.var).into_value()?;
+                    Some((index, value))
+                })?;
+        let (deferred, span) = self.0.remove(index);
+        Some((deferred, span, value))
+    }
+
+    // Run the first ready constraint until none are left, running one can make
+    // others ready
+    pub(super) fn run(
+        &mut self,
+        unifier: &mut Unifier<T>,
+    ) -> Result<(), (T::Error, usize)> {
+        while let Some((deferred, span, value)) = self.take_ready(unifier) {
+            (deferred.constraint)(unifier, &value)
+                .map_err(|error| (error, span))?;
+        }
+        Ok(())
+    }
+
+    // Like run but each constraint runs in its own snapshot, one which fails
+    // is rolled back and its error passed to on_error
+    pub(super) fn run_lenient(
+        &mut self,
+        unifier: &mut Unifier<T>,
+        mut on_error: impl FnMut(T::Error),
+    ) {
+        while let Some((deferred, _, value)) = self.take_ready(unifier) {
+            let snapshot = unifier.0.snapshot();
+            match (deferred.constraint)(unifier, &value) {
+                Ok(()) => unifier.0.commit(snapshot),
+                Err(error) => {
+                    unifier.0.rollback_to(snapshot);
+                    on_error(error);
+                }
+            }
+        }
+    }
+
+    // Unify each constraint in turn, running deferred constraints as they
+    // become ready. Errors are paired with the index of the span of the
+    // constraint which failed, constraints' spans come first
+    pub(super) fn solve(
+        mut self,
+        unifier: &mut Unifier<T>,
+        constraints: impl IntoIterator<Item = Constraint<T>>,
+    ) -> Result<(), (T::Error, usize)> {
+        self.run(unifier)?;
+        for (index, constraint) in constraints.into_iter().enumerate() {
+            self.step(unifier, index, constraint)?;
+        }
+        self.finish()
+    }
+
+    // Unify the constraint with the given span index, then anything deferred
+    // which it made ready
+    pub(super) fn step(
+        &mut self,
+        unifier: &mut Unifier<T>,
+        index: usize,
+        (left, right): Constraint<T>,
+    ) -> Result<(), (T::Error, usize)> {
+        unifier
+            .dispatch(left, right)
+            .map_err(|error| (error, index))?;
+        self.run(unifier)
+    }
+
+    // An error for each constraint which never ran, in the order they were
+    // deferred
+    pub(super) fn unresolved(self) -> impl Iterator<Item = (T::Error, usize)> {
+        self.0.into_iter().map(|(deferred, span)| {
+            let error = UnresolvedVariableError(deferred.var);
+            ((deferred.unresolved)(error), span)
+        })
+    }
+
+    // Report the first constraint which never ran
+    pub(super) fn finish(self) -> Result<(), (T::Error, usize)> {
+        match self.unresolved().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Recording how variables got their values, see [`Table::with_history`]
+
+use std::collections::HashMap;
+
+use value_type::value_type;
+
+use super::{Table, Unifier, Unify, ValueOrVar, Var};
+
+// See Table::unify_with_history
+type WithHistory<T> = (
+    Result<HashMap<Var, ValueOrVar<T>>, <T as Unify>::Error>,
+    Vec<Step<T>>,
+);
+
+/// One step taken while unifying, recorded by a table built with
+/// [`TableBuilder::history`](super::TableBuilder::history)
+///
+/// The steps taken for a constraint follow the [`Constraint`](Step::Constraint)
+/// step which started them, so each binding can be traced back to the
+/// constraint responsible for it
+#[value_type]
+pub enum Step<T> {
+    /// A constraint started being unified, before either side was normalized
+    Constraint {
+        /// Left hand side
+        left: ValueOrVar<T>,
+        /// Right hand side
+        right: ValueOrVar<T>,
+    },
+    /// Two variables were unified, see [`Unifier::unify_var_var`]
+    VarVar {
+        /// First variable
+        left: Var,
+        /// Second variable
+        right: Var,
+    },
+    /// A variable was unified with a value, see [`Unifier::unify_var_value`]
+    VarValue {
+        /// The variable
+        var: Var,
+        /// The value
+        value: T,
+    },
+    /// The values of two variables met and were combined by [`Unify::merge`]
+    /// or [`Unify::join`], after one of the steps above
+    Merge {
+        /// The value the left hand variable had
+        left: T,
+        /// The value on the right hand side
+        right: T,
+        /// The combined value
+        result: T,
+    },
+}
+
+impl<T: Unify> Table<T> {
+    /// Construct a table which records every unification step, see
+    /// [`TableBuilder::history`](super::TableBuilder::history)
+    #[must_use]
+    pub fn with_history() -> Self {
+        Self::builder().history().build()
+    }
+}
+
+impl<T: Unify, S> Table<T, S> {
+    /// The steps recorded so far, in the order they were taken
+    ///
+    /// Only [`constraint_now`](Table::constraint_now) unifies without
+    /// consuming the table, use [`unify_with_history`] to get the steps taken
+    /// by a full unification. Always empty unless the table was built with
+    /// [`TableBuilder::history`](super::TableBuilder::history)
+    ///
+    /// [`unify_with_history`]: Table::unify_with_history
+    #[must_use]
+    pub fn history(&self) -> &[Step<T>] {
+        self.history.as_deref().unwrap_or_default()
+    }
+
+    /// Perform unification, also returning the steps taken, see
+    /// [`Table::history`]
+    ///
+    /// The steps are returned even if unification fails, in which case the
+    /// last [`Constraint`](Step::Constraint) step is the one which failed
+    pub fn unify_with_history(self) -> WithHistory<T> {
+        let (result, history) = self.unify_recorded();
+        (result.map_err(|e| e.error), history)
+    }
+}
+
+impl<T: Unify> Unifier<T> {
+    pub(super) fn recording(&self) -> bool {
+        self.0.history.is_some()
+    }
+
+    // The step is only built if the table is recording
+    pub(super) fn record(&mut self, step: impl FnOnce() -> Step<T>) {
+        if let Some(history) = &mut self.0.history {
+            history.push(step());
+        }
+    }
+
+    // `var` had the value `left` before it was unified with `right`, the
+    // result is whatever it has now
+    pub(super) fn record_merge(&mut self, var: Var, left: T, right: T) {
+        if let ValueOrVar::Value(result) = self.probe(var) {
+            self.record(|| Step::Merge {
+                left,
+                right,
+                result,
+            });
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Let-polymorphism, see [`Table::generalize`]
+
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+};
+
+use value_type::value_type;
+
+use super::{Table, Unify, ValueOrVar, Var};
+
+/// A value quantified over some of its variables, produced by
+/// [`Table::generalize`]
+///
+/// Each use of the scheme should be passed through [`Table::instantiate`] to
+/// get a copy with fresh variables
+#[value_type]
+pub struct Scheme<T> {
+    /// The quantified variables, in the order they first appear in `value`
+    pub vars: Vec<Var>,
+    /// The generalized value. Variables which have been unified with a value
+    /// are replaced by it, the rest by their representative
+    pub value: ValueOrVar<T>,
+}
+
+impl<T: Unify, S> Table<T, S> {
+    /// Generalize `value` over the variables it contains which haven't been
+    /// unified with a value and don't occur in `env_vars`, or the values they
+    /// have been unified with
+    ///
+    /// The variables are found using [`Unify::children`], so `T` must
+    /// implement it for anything to be quantified
+    pub fn generalize(
+        &mut self,
+        value: ValueOrVar<T>,
+        env_vars: &HashSet<Var>,
+    ) -> Scheme<T> {
+        // Anything reachable from the environment escapes, including through
+        // variables it has already been unified with a value
+        let mut env = Vec::new();
+        for &var in env_vars {
+            let value = self.zonk(ValueOrVar::Var(var));
+            free_vars(&value, &mut env);
+        }
+        let value = self.zonk(value);
+        let mut vars = Vec::new();
+        free_vars(&value, &mut vars);
+        vars.retain(|var| !env.contains(var));
+        Scheme { vars, value }
+    }
+
+    /// Generalize `value` over the variables it contains which haven't been
+    /// unified with a value and whose [level](Table::var_at_level) is above
+    /// `current_level`
+    ///
+    /// Unlike [`Table::generalize`] this doesn't need to look at the
+    /// environment, any variable which escaped into it has had its level
+    /// lowered to the environment's
+    pub fn generalize_level(
+        &mut self,
+        value: ValueOrVar<T>,
+        current_level: u32,
+    ) -> Scheme<T> {
+        let value = self.zonk(value);
+        let mut vars = Vec::new();
+        free_vars(&value, &mut vars);
+        vars.retain(|&var| self.level(var) > current_level);
+        Scheme { vars, value }
+    }
+
+    /// Copy the scheme's value, replacing each of its quantified variables
+    /// with a fresh one
+    pub fn instantiate(&mut self, scheme: &Scheme<T>) -> ValueOrVar<T> {
+        self.instantiate_level(scheme, 0)
+    }
+
+    /// Like [`instantiate`](Table::instantiate) but the fresh variables are
+    /// created at `level`, see [`Table::var_at_level`]
+    pub fn instantiate_level(
+        &mut self,
+        scheme: &Scheme<T>,
+        level: u32,
+    ) -> ValueOrVar<T> {
+        let fresh = scheme
+            .vars
+            .iter()
+            .map(|&var| (var, self.var_at_level(level)))
+            .collect::<HashMap<_, _>>();
+        rename(scheme.value.clone(), &fresh)
+    }
+
+    // Replace every variable nested in the value with its current value, or
+    // its representative if it doesn't have one
+    pub(super) fn zonk(&mut self, value: ValueOrVar<T>) -> ValueOrVar<T> {
+        let offset = self.options.var_offset;
+        let mut value = match value {
+            ValueOrVar::Value(value) => value,
+            ValueOrVar::Var(var) => {
+                let var = var.annotate(offset);
+                match self.table().probe_value(var).value {
+                    Some(value) => value,
+                    None => {
+                        return ValueOrVar::Var(
+                            self.table().find(var).erase(offset),
+                        );
+                    }
+                }
+            }
+        };
+        for child in value.children_mut() {
+            *child = self.zonk(mem::replace(child, ValueOrVar::Var(Var(0))));
+        }
+        ValueOrVar::Value(value)
+    }
+}
+
+// Variables in order of first appearance, without duplicates
+pub(super) fn free_vars<T: Unify>(value: &ValueOrVar<T>, vars: &mut Vec<Var>) {
+    match value {
+        ValueOrVar::Var(var) => {
+            if !vars.contains(var) {
+                vars.push(*var);
+            }
+        }
+        ValueOrVar::Value(value) => {
+            for child in value.children() {
+                free_vars(child, vars);
+            }
+        }
+    }
+}
+
+fn rename<T: Unify>(
+    value: ValueOrVar<T>,
+    fresh: &HashMap<Var, Var>,
+) -> ValueOrVar<T> {
+    match value {
+        ValueOrVar::Var(var) => {
+            ValueOrVar::Var(fresh.get(&var).copied().unwrap_or(var))
+        }
+        ValueOrVar::Value(mut value) => {
+            for child in value.children_mut() {
+                *child =
+                    rename(mem::replace(child, ValueOrVar::Var(Var(0))), fresh);
+            }
+            ValueOrVar::Value(value)
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Plain data copy of a [`Table`], see [`Table::export`]
+
+use value_type::value_type;
+
+use super::{Options, Table, Unify, Value, ValueOrVar, Var};
+
+/// The observable state of a [`Table`], produced by [`Table::export`] and
+/// restored by [`Table::import`]
+///
+/// With the `serde` feature this can be serialized, e.g. to resume inference
+/// in a later process
+#[value_type]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TableState<T, S = ()> {
+    /// The table's [variable offset](super::TableBuilder::var_offset)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub var_offset: u32,
+    /// The representative of each variable in the table, in order
+    pub representatives: Vec<Var>,
+    /// The value of each representative which has one, in ascending order
+    pub values: Vec<(Var, T)>,
+    /// Names given by [`Table::named_var`], in ascending order
+    pub names: Vec<(Var, String)>,
+    /// The constraints added so far with their weights and spans, in the
+    /// order they were added
+    pub constraints: Vec<(ValueOrVar<T>, ValueOrVar<T>, u32, S)>,
+}
+
+impl<T: Unify, S> Table<T, S> {
+    /// Copy the variables, their values and the constraints out of the table
+    ///
+    /// Options from the [builder](Table::builder) other than the variable
+    /// offset, levels and deferred constraints aren't included
+    #[must_use]
+    pub fn export(&self) -> TableState<T, S>
+    where
+        S: Clone,
+    {
+        let offset = self.options.var_offset;
+        let vars = self.get_vars();
+        let mut table = self.unification_table.borrow_mut();
+        let representatives = vars
+            .iter()
+            .map(|&var| table.find(var.annotate::<T>(offset)).erase(offset))
+            .collect::<Vec<_>>();
+        let values = vars
+            .iter()
+            .zip(&representatives)
+            .filter(|(var, root)| var == root)
+            .filter_map(|(&var, _)| {
+                let value = table.probe_value(var.annotate(offset)).value?;
+                Some((var, value))
+            })
+            .collect();
+        let mut names = self
+            .names
+            .iter()
+            .map(|(&var, name)| (var, name.clone()))
+            .collect::<Vec<_>>();
+        names.sort_by_key(|&(Var(var), _)| var);
+        let constraints = self
+            .constraints
+            .iter()
+            .zip(&self.spans)
+            .map(|(((left, right), weight), span)| {
+                (left.clone(), right.clone(), *weight, span.clone())
+            })
+            .collect();
+        TableState {
+            var_offset: offset,
+            representatives,
+            values,
+            names,
+            constraints,
+        }
+    }
+
+    /// Rebuild a table from [`Table::export`]
+    ///
+    /// Each variable keeps its representative and value, though the internal
+    /// structure of the table may differ from the original
+    ///
+    /// # Panics
+    /// If the state refers to variables beyond the end of
+    /// `state.representatives`
+    #[must_use]
+    pub fn import(state: TableState<T, S>) -> Self {
+        let TableState {
+            var_offset: offset,
+            representatives,
+            values,
+            names,
+            constraints,
+        } = state;
+        let mut table = Self::with_options(Options {
+            var_offset: offset,
+            ..Options::default()
+        });
+        let vars = table.vars(representatives.len());
+        for (var, root) in vars.into_iter().zip(representatives) {
+            // Linking a lone variable to a root keeps the root as the
+            // representative, and nothing has a value yet
+            let Ok(()) =
+                table.link(var.annotate(offset), root.annotate(offset))
+            else {
+                unreachable!("Linking unbound variables can't fail")
+            };
+        }
+        for (var, value) in values {
+            let Ok(()) = table
+                .table()
+                .unify_var_value(var.annotate(offset), Value::bound(value))
+            else {
+                unreachable!("Binding an unbound variable can't fail")
+            };
+        }
+        table.names = names.into_iter().collect();
+        for (left, right, weight, span) in constraints {
+            table.constraints.push(((left, right), weight));
+            table.spans.push(span);
+        }
+        table
+    }
+}
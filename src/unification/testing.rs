@@ -0,0 +1,72 @@
+//! Helpers for testing [`Unify`] implementations, enabled by the `testing`
+//! feature
+
+use std::fmt::Debug;
+
+use super::{Table, Unifier, Unify, ValueOrVar, Var};
+
+/// Drives a unification table one constraint at a time, panicking with a
+/// readable message when a constraint doesn't behave as expected
+#[expect(missing_debug_implementations)]
+pub struct Harness<T: Unify>(Unifier<T>);
+
+impl<T: Unify> Default for Harness<T> {
+    fn default() -> Self {
+        Self(Unifier(Table::new()))
+    }
+}
+
+impl<T: Unify> Harness<T> {
+    /// Constructor
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a fresh unification variable, see [`Table::var`]
+    pub fn var(&mut self) -> Var {
+        self.0.0.var()
+    }
+
+    /// Unify `left` and `right` immediately
+    ///
+    /// # Panics
+    /// If they fail to unify
+    #[track_caller]
+    pub fn assert_unifies(&mut self, left: ValueOrVar<T>, right: ValueOrVar<T>)
+    where
+        T::Error: Debug,
+    {
+        let message = format!("{left:?} = {right:?}");
+        if let Err(error) = self.0.dispatch(left, right) {
+            panic!("Expected {message} to unify, failed with {error:?}");
+        }
+    }
+
+    /// Try to unify `left` and `right`, returning the error. Anything the
+    /// attempt changed is rolled back
+    ///
+    /// # Panics
+    /// If they unify successfully
+    #[track_caller]
+    pub fn assert_conflict(
+        &mut self,
+        left: ValueOrVar<T>,
+        right: ValueOrVar<T>,
+    ) -> T::Error {
+        let message = format!("{left:?} = {right:?}");
+        let snapshot = self.0.0.snapshot();
+        let result = self.0.dispatch(left, right);
+        self.0.0.rollback_to(snapshot);
+        match result {
+            Ok(()) => panic!("Expected {message} to conflict"),
+            Err(error) => error,
+        }
+    }
+
+    /// The variable's value so far with any variables nested inside it
+    /// resolved too. Unbound variables resolve to their representative
+    pub fn resolved(&mut self, var: Var) -> ValueOrVar<T> {
+        self.0.0.zonk(ValueOrVar::Var(var))
+    }
+}
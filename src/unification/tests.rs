@@ -1 +1,24 @@
+mod canonicalize;
+mod deferred;
+mod depth;
+#[cfg(feature = "derive")]
+mod derive;
+mod history;
+mod invariants;
 mod lambda;
+mod normalize;
+mod pretty;
+mod scheme;
+#[cfg(feature = "serde")]
+mod serde;
+mod state;
+mod subtyping;
+mod table;
+#[cfg(feature = "testing")]
+mod testing;
+mod tuple;
+mod value_or_var;
+
+// Only the serde tests use it
+#[cfg(not(feature = "serde"))]
+use serde_json as _;
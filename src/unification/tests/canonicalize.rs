@@ -0,0 +1,66 @@
+use std::borrow::Cow;
+
+use value_type::value_type;
+
+use crate::unification::{Table, Unifier, Unify, ValueOrVar};
+
+// Field order doesn't matter, but merge only compares structurally
+#[value_type]
+struct Record(Vec<&'static str>);
+
+#[value_type]
+struct Mismatch;
+
+impl Unify for Record {
+    type Error = Mismatch;
+
+    fn unify(
+        left: ValueOrVar<Self>,
+        right: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> Result<(), Self::Error> {
+        match (left, right) {
+            (ValueOrVar::Var(left), ValueOrVar::Var(right)) => {
+                unifier.unify_var_var(left, right)
+            }
+            (ValueOrVar::Var(var), ValueOrVar::Value(value))
+            | (ValueOrVar::Value(value), ValueOrVar::Var(var)) => {
+                unifier.unify_var_value(var, value)
+            }
+            (ValueOrVar::Value(left), ValueOrVar::Value(right)) => {
+                Self::merge(&left, &right).map(|_| ())
+            }
+        }
+    }
+
+    fn merge(left: &Self, right: &Self) -> Result<Self, Self::Error> {
+        if left == right {
+            Ok(left.clone())
+        } else {
+            Err(Mismatch)
+        }
+    }
+
+    fn canonicalize(&self) -> Cow<'_, Self> {
+        let mut fields = self.0.clone();
+        fields.sort_unstable();
+        Cow::Owned(Record(fields))
+    }
+}
+
+#[test]
+fn canonicalize() -> Result<(), Mismatch> {
+    let mut table = Table::new();
+    let [a, b] = table.fresh_array();
+    table.constraint(
+        ValueOrVar::Var(a),
+        ValueOrVar::Value(Record(vec!["x", "y"])),
+    );
+    table.constraint(
+        ValueOrVar::Var(b),
+        ValueOrVar::Value(Record(vec!["y", "x"])),
+    );
+    table.constraint(ValueOrVar::Var(a), ValueOrVar::Var(b));
+    let _ = table.unify()?;
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use pretty_assertions::assert_eq;
+use value_type::value_type;
+
+use crate::unification::{
+    SpannedError, Table, Unifier, Unify, UnresolvedVariableError, ValueOrVar,
+    Var,
+};
+
+// The lambda calculus types, with an error for deferred constraints which
+// never run
+#[value_type]
+enum Type {
+    Unit,
+    Function {
+        arg: Box<ValueOrVar<Type>>,
+        ret: Box<ValueOrVar<Type>>,
+    },
+}
+
+#[value_type]
+enum TypeError {
+    IncompatibleTypes(Type, Type),
+    Unresolved(UnresolvedVariableError),
+}
+
+impl From<UnresolvedVariableError> for TypeError {
+    fn from(error: UnresolvedVariableError) -> Self {
+        TypeError::Unresolved(error)
+    }
+}
+
+impl From<Var> for ValueOrVar<Type> {
+    fn from(var: Var) -> Self {
+        Self::Var(var)
+    }
+}
+
+impl Unify for Type {
+    type Error = TypeError;
+
+    fn unify(
+        left: ValueOrVar<Self>,
+        right: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> Result<(), Self::Error> {
+        match (left, right) {
+            (ValueOrVar::Var(left), ValueOrVar::Var(right)) => {
+                unifier.unify_var_var(left, right)
+            }
+            (ValueOrVar::Var(var), ValueOrVar::Value(value))
+            | (ValueOrVar::Value(value), ValueOrVar::Var(var)) => {
+                unifier.unify_var_value(var, value)
+            }
+            (
+                ValueOrVar::Value(Type::Function { arg: a1, ret: r1 }),
+                ValueOrVar::Value(Type::Function { arg: a2, ret: r2 }),
+            ) => {
+                Self::unify(*a1, *a2, unifier)?;
+                Self::unify(*r1, *r2, unifier)
+            }
+            (ValueOrVar::Value(left), ValueOrVar::Value(right)) => {
+                Self::merge(&left, &right).map(|_| ())
+            }
+        }
+    }
+
+    fn merge(left: &Self, right: &Self) -> Result<Self, Self::Error> {
+        if left == right {
+            Ok(left.clone())
+        } else {
+            Err(TypeError::IncompatibleTypes(left.clone(), right.clone()))
+        }
+    }
+}
+
+fn unit() -> ValueOrVar<Type> {
+    ValueOrVar::Value(Type::Unit)
+}
+
+fn function(
+    arg: impl Into<ValueOrVar<Type>>,
+    ret: impl Into<ValueOrVar<Type>>,
+) -> ValueOrVar<Type> {
+    ValueOrVar::Value(Type::Function {
+        arg: Box::new(arg.into()),
+        ret: Box::new(ret.into()),
+    })
+}
+
+#[test]
+fn defer() {
+    let mut table = Table::new();
+    let [f, ret, result, chained] = table.fresh_array();
+    // Once f is known to be a function, its return type is ret
+    table.defer(f, move |unifier, typ| {
+        let Type::Function { ret: actual, .. } = typ else {
+            return Err(TypeError::IncompatibleTypes(typ.clone(), Type::Unit));
+        };
+        match actual.as_ref() {
+            ValueOrVar::Var(var) => unifier.unify_var_var(ret, *var),
+            ValueOrVar::Value(value) => unifier.unify_var_value_ref(ret, value),
+        }
+    });
+    // Only ready once the constraint above has run
+    table.defer(ret, move |unifier, typ| {
+        unifier.unify_var_value(chained, typ.clone())
+    });
+    table.constraint(f.into(), function(unit(), result));
+    table.constraint(result.into(), unit());
+
+    let Ok(types) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+    assert_eq!(unit(), types[&ret]);
+    assert_eq!(unit(), types[&chained]);
+}
+
+#[test]
+fn never_fires() {
+    let mut table = Table::<Type>::new();
+    let [a, b] = table.fresh_array();
+    table.defer(a, |_, _| Ok(()));
+    table.constraint(a.into(), b.into());
+
+    assert_eq!(
+        Err(TypeError::Unresolved(UnresolvedVariableError(a))),
+        table.unify().map(|_| ())
+    );
+}
+
+#[test]
+fn spanned() {
+    let mut table = Table::<Type, &str>::new_spanned();
+    let [a, b] = table.fresh_array();
+    table.defer_spanned(a, "deferred", move |unifier, typ| {
+        unifier.unify_var_value(b, typ.clone())
+    });
+    table.constraint_spanned(b.into(), unit(), "line 1");
+    table.constraint_spanned(a.into(), function(unit(), unit()), "line 2");
+
+    let Err(SpannedError { error, span }) = table.unify_spanned() else {
+        panic!("Expected unification to fail")
+    };
+    assert!(matches!(error, TypeError::IncompatibleTypes(..)));
+    assert_eq!("deferred", span);
+}
+
+#[test]
+fn constraint_now() {
+    let mut table = Table::<Type>::new();
+    let [a, b, c] = table.fresh_array();
+    table.defer(a, move |unifier, typ| {
+        unifier.unify_var_value(b, typ.clone())
+    });
+    table.defer(c, |_, _| Ok(()));
+    assert_eq!(
+        Ok(()),
+        table.constraint_now(b.into(), function(unit(), unit()))
+    );
+
+    // The deferred constraint runs and fails, so nothing changes
+    assert!(matches!(
+        table.constraint_now(a.into(), unit()),
+        Err(TypeError::IncompatibleTypes(..))
+    ));
+    assert_eq!(ValueOrVar::Var(a), table.zonk(a.into()));
+
+    assert_eq!(
+        Ok(()),
+        table.constraint_now(a.into(), function(unit(), unit()))
+    );
+    assert_eq!(function(unit(), unit()), table.zonk(b.into()));
+    // The constraint on c is still waiting
+    assert_eq!(
+        Err(TypeError::Unresolved(UnresolvedVariableError(c))),
+        table.unify().map(|_| ())
+    );
+}
+
+// b is deferred on a and conflicts with the constraint on b
+fn conflicting() -> (Table<Type>, [Var; 3]) {
+    let mut table = Table::<Type>::new();
+    let [a, b, never] = table.fresh_array();
+    table.defer(a, move |unifier, typ| {
+        unifier.unify_var_value(b, typ.clone())
+    });
+    table.defer(never, |_, _| Ok(()));
+    table.constraint(b.into(), function(unit(), unit()));
+    table.constraint(a.into(), unit());
+    (table, [a, b, never])
+}
+
+#[test]
+fn min_conflict() {
+    let (table, [a, b, _]) = conflicting();
+    let (types, dropped) = table.unify_min_conflict();
+    assert!(dropped.is_empty());
+    assert_eq!(unit(), types[&a]);
+    assert_eq!(function(unit(), unit()), types[&b]);
+}
+
+#[test]
+fn recovery() {
+    let (table, [a, b, never]) = conflicting();
+    let (types, errors) = table.unify_with_recovery();
+    assert!(matches!(
+        errors.as_slice(),
+        [
+            TypeError::IncompatibleTypes(..),
+            TypeError::Unresolved(UnresolvedVariableError(var))
+        ] if *var == never
+    ));
+    assert_eq!(unit(), types[&a]);
+    assert_eq!(function(unit(), unit()), types[&b]);
+}
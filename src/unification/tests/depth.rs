@@ -0,0 +1,185 @@
+use pretty_assertions::assert_eq;
+use value_type::value_type;
+
+use super::lambda::{
+    builders::{mono_typ, typ},
+    implementation::Type,
+};
+use crate::unification::{
+    DepthLimitError, OccursError, Table, Unifier, Unify, ValueOrVar,
+};
+
+fn type_children(typ: &Type) -> Vec<ValueOrVar<Type>> {
+    match typ {
+        Type::Unit => Vec::new(),
+        Type::Function { arg, ret } => {
+            vec![arg.as_ref().clone(), ret.as_ref().clone()]
+        }
+    }
+}
+
+#[test]
+fn value_depth() {
+    let mut unifier = Unifier(Table::new());
+    let a = unifier.0.var();
+    let b = unifier.0.var();
+    let Ok(()) = unifier.unify_var_value(a, Type::Unit) else {
+        panic!("Expected unification to succeed")
+    };
+
+    // (() -> ()) -> b, b is unbound so it doesn't add anything
+    let nested = typ::function(typ::function(typ::unit(), typ::unit()), b);
+    assert_eq!(3, unifier.value_depth(nested, type_children));
+    // a is bound to (), which is 1 deep
+    let bound = typ::function(typ::unit(), typ::function(a, typ::unit()));
+    assert_eq!(3, unifier.value_depth(bound, type_children));
+    assert_eq!(1, unifier.value_depth(a.into(), type_children));
+    assert_eq!(0, unifier.value_depth(b.into(), type_children));
+}
+
+#[test]
+fn occurs() {
+    let mut unifier = Unifier(Table::new());
+    let [a, b, c] = unifier.0.fresh_array();
+    let Ok(()) = unifier.unify_var_value(b, mono_typ::function(a, typ::unit()))
+    else {
+        panic!("Expected unification to succeed")
+    };
+
+    assert!(unifier.occurs(a, &mono_typ::function(typ::unit(), a)));
+    assert!(!unifier.occurs(a, &mono_typ::function(c, typ::unit())));
+    assert!(!unifier.occurs(a, &mono_typ::unit()));
+    // Found through b, which is already bound to a type containing a
+    assert!(unifier.occurs(a, &mono_typ::function(typ::unit(), b)));
+
+    // Found through a variable unified with a
+    let Ok(()) = unifier.unify_var_var(a, c) else {
+        panic!("Expected unification to succeed")
+    };
+    assert!(unifier.occurs(a, &mono_typ::function(c, typ::unit())));
+}
+
+#[value_type]
+enum Nested {
+    Leaf,
+    Node(Box<ValueOrVar<Nested>>),
+}
+
+#[value_type]
+enum NestedError {
+    Mismatch,
+    TooDeep(DepthLimitError),
+    Occurs(OccursError),
+}
+
+impl From<DepthLimitError> for NestedError {
+    fn from(error: DepthLimitError) -> Self {
+        NestedError::TooDeep(error)
+    }
+}
+
+impl From<OccursError> for NestedError {
+    fn from(error: OccursError) -> Self {
+        NestedError::Occurs(error)
+    }
+}
+
+impl Unify for Nested {
+    type Error = NestedError;
+
+    fn unify(
+        left: ValueOrVar<Self>,
+        right: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> Result<(), Self::Error> {
+        match (left, right) {
+            (ValueOrVar::Var(left), ValueOrVar::Var(right)) => {
+                unifier.unify_var_var(left, right)
+            }
+            (ValueOrVar::Var(var), ValueOrVar::Value(value))
+            | (ValueOrVar::Value(value), ValueOrVar::Var(var)) => {
+                unifier.unify_var_value(var, value)
+            }
+            (
+                ValueOrVar::Value(Nested::Leaf),
+                ValueOrVar::Value(Nested::Leaf),
+            ) => Ok(()),
+            (
+                ValueOrVar::Value(Nested::Node(left)),
+                ValueOrVar::Value(Nested::Node(right)),
+            ) => Self::unify(*left, *right, unifier),
+            _ => Err(NestedError::Mismatch),
+        }
+    }
+
+    fn children(&self) -> impl Iterator<Item = &ValueOrVar<Self>> {
+        match self {
+            Nested::Leaf => None,
+            Nested::Node(inner) => Some(&**inner),
+        }
+        .into_iter()
+    }
+
+    fn merge(left: &Self, right: &Self) -> Result<Self, Self::Error> {
+        if left == right {
+            Ok(left.clone())
+        } else {
+            Err(NestedError::Mismatch)
+        }
+    }
+}
+
+fn nested_children(nested: &Nested) -> Vec<ValueOrVar<Nested>> {
+    match nested {
+        Nested::Leaf => Vec::new(),
+        Nested::Node(inner) => vec![inner.as_ref().clone()],
+    }
+}
+
+fn nest(depth: usize) -> Nested {
+    (1..depth).fold(Nested::Leaf, |inner, _| {
+        Nested::Node(Box::new(ValueOrVar::Value(inner)))
+    })
+}
+
+#[test]
+fn depth_limit() {
+    let mut table = Table::builder().depth_limit(3, nested_children).build();
+    let a = table.var();
+    table.constraint(ValueOrVar::Var(a), ValueOrVar::Value(nest(3)));
+    let Ok(result) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+    assert_eq!(ValueOrVar::Value(nest(3)), result[&a]);
+
+    let mut table = Table::builder().depth_limit(3, nested_children).build();
+    let a = table.var();
+    table.constraint(ValueOrVar::Var(a), ValueOrVar::Value(nest(4)));
+    assert_eq!(
+        Err(NestedError::TooDeep(DepthLimitError { depth: 4, limit: 3 })),
+        table.unify().map(|_| ())
+    );
+}
+
+#[test]
+fn occurs_check() {
+    let node = |var| Nested::Node(Box::new(ValueOrVar::Var(var)));
+
+    let mut table = Table::builder().occurs_check().build();
+    let a = table.var();
+    table.constraint(ValueOrVar::Var(a), ValueOrVar::Value(node(a)));
+    assert_eq!(
+        Err(NestedError::Occurs(OccursError(a))),
+        table.unify().map(|_| ())
+    );
+
+    // Found through a variable which is already bound
+    let mut table = Table::builder().occurs_check().build();
+    let [a, b] = table.fresh_array();
+    table.constraint(ValueOrVar::Var(a), ValueOrVar::Value(node(b)));
+    table.constraint(ValueOrVar::Var(b), ValueOrVar::Value(node(a)));
+    assert_eq!(
+        Err(NestedError::Occurs(OccursError(b))),
+        table.unify().map(|_| ())
+    );
+}
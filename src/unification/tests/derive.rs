@@ -0,0 +1,187 @@
+use pretty_assertions::assert_eq;
+use value_type::value_type;
+
+use qualified::{Mismatch, Tree};
+
+use crate::unification::{Table, Unify, ValueOrVar, Var};
+
+// Covers plain fields, recursive fields and struct and tuple variants
+#[value_type]
+#[derive(Unify)]
+#[unify(
+    crate = crate,
+    error = TypeError,
+    merge = Type::equal,
+    occurs = TypeError::Infinite
+)]
+enum Type {
+    Unit,
+    Named(String),
+    Function {
+        arg: Box<ValueOrVar<Type>>,
+        ret: Box<ValueOrVar<Type>>,
+    },
+    Pair(Box<ValueOrVar<Type>>, Box<ValueOrVar<Type>>),
+}
+
+#[value_type]
+enum TypeError {
+    Mismatch(Type, Type),
+    Infinite(Var, Type),
+}
+
+impl Type {
+    fn equal(left: &Self, right: &Self) -> Result<Self, TypeError> {
+        if left == right {
+            Ok(left.clone())
+        } else {
+            Err(TypeError::Mismatch(left.clone(), right.clone()))
+        }
+    }
+}
+
+fn value(typ: Type) -> ValueOrVar<Type> {
+    ValueOrVar::Value(typ)
+}
+
+fn function(arg: ValueOrVar<Type>, ret: ValueOrVar<Type>) -> ValueOrVar<Type> {
+    value(Type::Function {
+        arg: Box::new(arg),
+        ret: Box::new(ret),
+    })
+}
+
+fn pair(left: ValueOrVar<Type>, right: ValueOrVar<Type>) -> ValueOrVar<Type> {
+    value(Type::Pair(Box::new(left), Box::new(right)))
+}
+
+fn named(name: &str) -> ValueOrVar<Type> {
+    value(Type::Named(name.to_owned()))
+}
+
+#[test]
+fn structural() -> Result<(), TypeError> {
+    let mut table = Table::new();
+    let a = table.var();
+    let b = table.var();
+    let c = table.var();
+    table.constraint(
+        function(ValueOrVar::Var(a), pair(named("x"), ValueOrVar::Var(c))),
+        function(named("x"), ValueOrVar::Var(b)),
+    );
+    table.constraint(ValueOrVar::Var(c), value(Type::Unit));
+    let types = table.unify()?;
+    assert_eq!(types[&a], named("x"));
+    // Nested variables are left as they are, only the top level is resolved
+    assert_eq!(types[&b], pair(named("x"), ValueOrVar::Var(c)));
+    assert_eq!(types[&c], value(Type::Unit));
+    Ok(())
+}
+
+#[test]
+fn mismatch() {
+    let mut table = Table::new();
+    let a = table.var();
+    table.constraint(
+        pair(ValueOrVar::Var(a), named("x")),
+        pair(value(Type::Unit), named("y")),
+    );
+    assert_eq!(
+        table.unify(),
+        Err(TypeError::Mismatch(
+            Type::Named("x".to_owned()),
+            Type::Named("y".to_owned())
+        ))
+    );
+
+    let mut table = Table::new();
+    table.constraint(value(Type::Unit), function(named("x"), named("x")));
+    assert!(matches!(
+        table.unify(),
+        Err(TypeError::Mismatch(Type::Unit, _))
+    ));
+}
+
+#[test]
+fn occurs() {
+    let mut table = Table::new();
+    let a = table.var();
+    table.constraint(
+        ValueOrVar::Var(a),
+        function(ValueOrVar::Var(a), value(Type::Unit)),
+    );
+    assert!(
+        matches!(table.unify(), Err(TypeError::Infinite(var, _)) if var == a)
+    );
+}
+
+#[test]
+fn children() {
+    let a = Var(0);
+    let mut typ = Type::Function {
+        arg: Box::new(ValueOrVar::Var(a)),
+        ret: Box::new(value(Type::Unit)),
+    };
+    assert_eq!(
+        typ.children().cloned().collect::<Vec<_>>(),
+        vec![ValueOrVar::Var(a), value(Type::Unit)]
+    );
+    assert_eq!(typ.children_mut().count(), 2);
+    assert_eq!(Type::Named("x".to_owned()).children().count(), 0);
+}
+
+// Recursive fields written with full paths or through an alias. In a module
+// of its own so the paths aren't shortened by the imports above
+mod qualified {
+    use value_type::value_type;
+
+    use crate::unification::Unify;
+
+    pub(super) type Child = Box<crate::unification::ValueOrVar<Tree>>;
+
+    #[value_type]
+    #[derive(Unify)]
+    #[unify(crate = crate, error = Mismatch, merge = Tree::equal)]
+    pub(super) enum Tree {
+        Leaf(u32),
+        Node(
+            ::std::boxed::Box<crate::unification::ValueOrVar<Tree>>,
+            #[unify(recursive)] Child,
+        ),
+    }
+
+    #[value_type]
+    pub(super) struct Mismatch;
+
+    impl Tree {
+        pub(super) fn equal(
+            left: &Self,
+            right: &Self,
+        ) -> Result<Self, Mismatch> {
+            if left == right {
+                Ok(left.clone())
+            } else {
+                Err(Mismatch)
+            }
+        }
+    }
+}
+
+#[test]
+fn recursive_paths() -> Result<(), Mismatch> {
+    let node = |left, right| {
+        ValueOrVar::Value(Tree::Node(Box::new(left), Box::new(right)))
+    };
+    let leaf = |n| ValueOrVar::Value(Tree::Leaf(n));
+    let mut table = Table::new();
+    let a = table.var();
+    let b = table.var();
+    table.constraint(
+        node(leaf(1), ValueOrVar::Var(a)),
+        node(ValueOrVar::Var(b), leaf(2)),
+    );
+    let types = table.unify()?;
+    assert_eq!(types[&a], leaf(2));
+    assert_eq!(types[&b], leaf(1));
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use std::convert::Infallible;
+
+use pretty_assertions::assert_eq;
+use value_type::value_type;
+
+use super::lambda::{
+    builders::{mono_typ, typ},
+    implementation::TypeError,
+};
+use crate::unification::{Step, Table, Unifier, Unify, ValueOrVar};
+
+// Leaves variables alone in normalize so two bound variables reach
+// unify_var_var and have their values merged
+#[value_type(Copy)]
+struct Max(u32);
+
+impl Unify for Max {
+    type Error = Infallible;
+
+    fn unify(
+        left: ValueOrVar<Self>,
+        right: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> Result<(), Self::Error> {
+        match (left, right) {
+            (ValueOrVar::Var(left), ValueOrVar::Var(right)) => {
+                unifier.unify_var_var(left, right)
+            }
+            (ValueOrVar::Var(var), ValueOrVar::Value(value))
+            | (ValueOrVar::Value(value), ValueOrVar::Var(var)) => {
+                unifier.unify_var_value(var, value)
+            }
+            (ValueOrVar::Value(_), ValueOrVar::Value(_)) => Ok(()),
+        }
+    }
+
+    fn merge(left: &Self, right: &Self) -> Result<Self, Self::Error> {
+        Ok(Max(left.0.max(right.0)))
+    }
+
+    fn normalize(
+        value: ValueOrVar<Self>,
+        _: &mut Unifier<Self>,
+    ) -> ValueOrVar<Self> {
+        value
+    }
+}
+
+#[test]
+fn steps() {
+    let mut table = Table::with_history();
+    let a = table.var();
+    let b = table.var();
+    table.constraint(ValueOrVar::Var(a), ValueOrVar::Value(Max(1)));
+    table.constraint(ValueOrVar::Var(b), ValueOrVar::Value(Max(3)));
+    table.constraint(ValueOrVar::Var(a), ValueOrVar::Var(b));
+
+    let (types, history) = table.unify_with_history();
+
+    let Ok(types) = types;
+    assert_eq!(ValueOrVar::Value(Max(3)), types[&a]);
+    assert_eq!(
+        vec![
+            Step::Constraint {
+                left: ValueOrVar::Var(a),
+                right: ValueOrVar::Value(Max(1))
+            },
+            Step::VarValue {
+                var: a,
+                value: Max(1)
+            },
+            Step::Constraint {
+                left: ValueOrVar::Var(b),
+                right: ValueOrVar::Value(Max(3))
+            },
+            Step::VarValue {
+                var: b,
+                value: Max(3)
+            },
+            Step::Constraint {
+                left: ValueOrVar::Var(a),
+                right: ValueOrVar::Var(b)
+            },
+            Step::VarVar { left: a, right: b },
+            Step::Merge {
+                left: Max(1),
+                right: Max(3),
+                result: Max(3)
+            },
+        ],
+        history
+    );
+}
+
+#[test]
+fn failure() {
+    let mut table = Table::with_history();
+    let a = table.var();
+    table.constraint(a.into(), typ::unit());
+    table.constraint(a.into(), typ::function(typ::unit(), typ::unit()));
+
+    let (types, history) = table.unify_with_history();
+
+    assert!(types.is_err());
+    // The failing constraint is the last one recorded and nothing is recorded
+    // after it
+    assert_eq!(
+        Some(&Step::Constraint {
+            left: a.into(),
+            right: typ::function(typ::unit(), typ::unit())
+        }),
+        history.last()
+    );
+    assert_eq!(
+        Some(&Step::VarValue {
+            var: a,
+            value: mono_typ::unit()
+        }),
+        history.iter().rev().nth(1)
+    );
+}
+
+#[test]
+fn constraint_now() {
+    let mut table = Table::with_history();
+    let a = table.var();
+    assert_eq!(Ok(()), table.constraint_now(a.into(), typ::unit()));
+    assert_eq!(2, table.history().len());
+    // Rolled back along with the rest of the failing constraint
+    assert!(matches!(
+        table.constraint_now(a.into(), typ::function(typ::unit(), typ::unit())),
+        Err(TypeError::IncompatibleTypes(..))
+    ));
+    assert_eq!(2, table.history().len());
+
+    // Nothing is recorded unless asked for
+    let mut table = Table::new();
+    let a = table.var();
+    assert_eq!(Ok(()), table.constraint_now(a.into(), typ::unit()));
+    assert!(table.history().is_empty());
+}
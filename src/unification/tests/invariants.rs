@@ -0,0 +1,92 @@
+use proptest::prelude::*;
+
+use crate::unification::{Table, Unifier, Unify, ValueOrVar};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Const(u8);
+
+#[derive(Debug)]
+struct Mismatch;
+
+impl Unify for Const {
+    type Error = Mismatch;
+
+    fn unify(
+        left: ValueOrVar<Self>,
+        right: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> Result<(), Self::Error> {
+        let result = match (left, right) {
+            (ValueOrVar::Var(left), ValueOrVar::Var(right)) => {
+                unifier.unify_var_var(left, right)
+            }
+            (ValueOrVar::Var(var), ValueOrVar::Value(value))
+            | (ValueOrVar::Value(value), ValueOrVar::Var(var)) => {
+                unifier.unify_var_value(var, value)
+            }
+            (ValueOrVar::Value(left), ValueOrVar::Value(right)) => {
+                Self::merge(&left, &right).map(|_| ())
+            }
+        };
+        // Check after every step, whether or not it succeeded
+        unifier.0.debug_check_invariants();
+        result
+    }
+
+    fn merge(left: &Self, right: &Self) -> Result<Self, Self::Error> {
+        if left == right {
+            Ok(*left)
+        } else {
+            Err(Mismatch)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Side {
+    Var(usize),
+    Value(u8),
+}
+
+fn side(vars: usize) -> impl Strategy<Value = Side> {
+    prop_oneof![
+        (0..vars).prop_map(Side::Var),
+        (0..3u8).prop_map(Side::Value)
+    ]
+}
+
+fn constraints() -> impl Strategy<Value = (usize, Vec<(Side, Side)>)> {
+    (1..10usize).prop_flat_map(|vars| {
+        (
+            Just(vars),
+            prop::collection::vec((side(vars), side(vars)), 0..20),
+        )
+    })
+}
+
+proptest! {
+    #[test]
+    fn random_unification((vars, constraints) in constraints()) {
+        let mut table = Table::new();
+        let vars = (0..vars).map(|_| table.var()).collect::<Vec<_>>();
+        let side = |side: &Side| match *side {
+            Side::Var(index) => ValueOrVar::Var(vars[index]),
+            Side::Value(value) => ValueOrVar::Value(Const(value)),
+        };
+        for (left, right) in &constraints {
+            table.constraint(side(left), side(right));
+        }
+        table.debug_check_invariants();
+
+        // If unification succeeded every constraint must hold in the result
+        if let Ok(result) = table.unify() {
+            let resolve = |side| match side {
+                ValueOrVar::Var(var) => result[&var].clone(),
+                value @ ValueOrVar::Value(_) => value,
+            };
+            for (left, right) in &constraints {
+                prop_assert_eq!(resolve(side(left)), resolve(side(right)));
+            }
+        }
+    }
+}
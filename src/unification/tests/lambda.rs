@@ -6,8 +6,8 @@ use self::{
 };
 use crate::unification::Var;
 
-mod builders;
-mod implementation;
+pub(super) mod builders;
+pub(super) mod implementation;
 
 macro_rules! set {
     ($($tt:tt)*) => {
@@ -145,6 +145,22 @@ impl Unify for Type {
         Unifier(unifier).unify_typ(left, right)
     }
 
+    fn children(&self) -> impl Iterator<Item = &ValueOrVar<Self>> {
+        match self {
+            Type::Unit => Vec::new(),
+            Type::Function { arg, ret } => vec![&**arg, &**ret],
+        }
+        .into_iter()
+    }
+
+    fn children_mut(&mut self) -> impl Iterator<Item = &mut ValueOrVar<Self>> {
+        match self {
+            Type::Unit => Vec::new(),
+            Type::Function { arg, ret } => vec![&mut **arg, &mut **ret],
+        }
+        .into_iter()
+    }
+
     // We only allow concrete types to unify if they are equal
     fn merge(left: &Self, right: &Self) -> Result<Self, Self::Error> {
         if left != right {
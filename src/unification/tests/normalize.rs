@@ -0,0 +1,73 @@
+use value_type::value_type;
+
+use crate::unification::{Table, Unifier, Unify, ValueOrVar};
+
+// A type which only compares structurally in unify, so it relies on the engine
+// to normalize nested variables before it gets to see them
+#[value_type]
+enum Tree {
+    Leaf,
+    Node(Box<ValueOrVar<Tree>>),
+}
+
+#[value_type]
+struct Mismatch;
+
+impl Unify for Tree {
+    type Error = Mismatch;
+
+    fn unify(
+        left: ValueOrVar<Self>,
+        right: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> Result<(), Self::Error> {
+        match (left, right) {
+            (ValueOrVar::Var(left), ValueOrVar::Var(right)) => {
+                unifier.unify_var_var(left, right)
+            }
+            (ValueOrVar::Var(var), ValueOrVar::Value(value))
+            | (ValueOrVar::Value(value), ValueOrVar::Var(var)) => {
+                unifier.unify_var_value(var, value)
+            }
+            (ValueOrVar::Value(left), ValueOrVar::Value(right)) => {
+                Self::merge(&left, &right).map(|_| ())
+            }
+        }
+    }
+
+    fn merge(left: &Self, right: &Self) -> Result<Self, Self::Error> {
+        if left == right {
+            Ok(left.clone())
+        } else {
+            Err(Mismatch)
+        }
+    }
+
+    fn normalize(
+        value: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> ValueOrVar<Self> {
+        // The unifier chases the top level variable, nested ones are left to
+        // the recursion
+        match unifier.normalize(value) {
+            ValueOrVar::Value(Tree::Node(child)) => ValueOrVar::Value(
+                Tree::Node(Box::new(Self::normalize(*child, unifier))),
+            ),
+            value => value,
+        }
+    }
+}
+
+#[test]
+fn nested() -> Result<(), Mismatch> {
+    let mut table = Table::new();
+    let a = table.var();
+    table.constraint(ValueOrVar::Var(a), ValueOrVar::Value(Tree::Leaf));
+    // Only equal once a has been replaced by Leaf inside the node
+    table.constraint(
+        ValueOrVar::Value(Tree::Node(Box::new(ValueOrVar::Var(a)))),
+        ValueOrVar::Value(Tree::Node(Box::new(ValueOrVar::Value(Tree::Leaf)))),
+    );
+    let _ = table.unify()?;
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use std::{collections::HashMap, fmt};
+
+use pretty_assertions::assert_eq;
+use value_type::value_type;
+
+use crate::unification::{Unifier, Unify, ValueOrVar, Var, pretty};
+
+#[value_type]
+enum Shape {
+    Point,
+    Group(Vec<Shape>),
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shape::Point => write!(f, "point"),
+            Shape::Group(shapes) => write!(f, "group of {}", shapes.len()),
+        }
+    }
+}
+
+impl Unify for Shape {
+    type Error = ();
+
+    fn unify(
+        _: ValueOrVar<Self>,
+        _: ValueOrVar<Self>,
+        _: &mut Unifier<Self>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn merge(left: &Self, _: &Self) -> Result<Self, Self::Error> {
+        Ok(left.clone())
+    }
+
+    fn fmt_pretty(&self, indent: usize) -> String {
+        match self {
+            Shape::Point => self.to_string(),
+            Shape::Group(shapes) => {
+                let mut result = String::from("group");
+                for shape in shapes {
+                    result.push('\n');
+                    result.push_str(&"  ".repeat(indent));
+                    result.push_str(&shape.fmt_pretty(indent + 1));
+                }
+                result
+            }
+        }
+    }
+}
+
+#[test]
+fn sorted_and_followed() {
+    let map = HashMap::from([
+        (Var(2), ValueOrVar::Var(Var(0))),
+        (Var(0), ValueOrVar::Value(Shape::Point)),
+        (Var(3), ValueOrVar::Var(Var(3))),
+        (
+            Var(1),
+            ValueOrVar::Value(Shape::Group(vec![
+                Shape::Point,
+                Shape::Group(vec![Shape::Point]),
+            ])),
+        ),
+    ]);
+
+    assert_eq!(
+        "Var(0) = point\n\
+         Var(1) = group\n  point\n  group\n    point\n\
+         Var(2) = point\n\
+         Var(3) = Var(3)\n",
+        pretty(&map)
+    );
+}
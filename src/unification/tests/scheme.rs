@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+
+use pretty_assertions::assert_eq;
+
+use super::lambda::{builders::typ, implementation::Type};
+use crate::unification::{Table, Unifier, ValueOrVar};
+
+#[test]
+fn generalize() {
+    let mut unifier = Unifier(Table::new());
+    let [a, b, c] = unifier.0.fresh_array();
+    let Ok(()) = unifier.unify_var_value(a, Type::Unit) else {
+        panic!("Expected unification to succeed")
+    };
+    let Ok(()) = unifier.unify_var_var(b, c) else {
+        panic!("Expected unification to succeed")
+    };
+    let ValueOrVar::Var(root) = unifier.probe(b) else {
+        panic!("Expected b to be unbound")
+    };
+
+    // a -> b, a is bound so only b is quantified
+    let value = typ::function(a, b);
+    let scheme = unifier.0.generalize(value.clone(), &HashSet::new());
+    assert_eq!(vec![root], scheme.vars);
+    assert_eq!(typ::function(typ::unit(), root), scheme.value);
+
+    // c escapes into the environment, so b can't be quantified
+    let scheme = unifier.0.generalize(value.clone(), &HashSet::from([c]));
+    assert!(scheme.vars.is_empty());
+
+    // Also when it escapes inside another variable's value
+    let env = unifier.0.var();
+    let Ok(()) = unifier.unify_var_value(
+        env,
+        Type::Function {
+            arg: Box::new(typ::unit()),
+            ret: Box::new(c.into()),
+        },
+    ) else {
+        panic!("Expected unification to succeed")
+    };
+    let scheme = unifier.0.generalize(value, &HashSet::from([env]));
+    assert!(scheme.vars.is_empty());
+}
+
+#[test]
+fn instantiate() {
+    let mut table = Table::<Type>::new();
+    let [a, b] = table.fresh_array();
+    let scheme = table.generalize(typ::function(a, b), &HashSet::from([b]));
+
+    let ValueOrVar::Value(Type::Function { arg, ret }) =
+        table.instantiate(&scheme)
+    else {
+        panic!("Expected a function")
+    };
+    let ValueOrVar::Var(fresh) = *arg else {
+        panic!("Expected a variable")
+    };
+    assert!(![a, b].contains(&fresh));
+    assert_eq!(ValueOrVar::Var(b), *ret);
+
+    // Each instantiation gets its own variables
+    let ValueOrVar::Value(Type::Function { arg, .. }) =
+        table.instantiate(&scheme)
+    else {
+        panic!("Expected a function")
+    };
+    assert_ne!(ValueOrVar::Var(fresh), *arg);
+}
+
+#[test]
+fn levels() {
+    let mut unifier = Unifier(Table::new());
+    let outer = unifier.0.var_at_level(1);
+    let [escaped, inner, nested] = [2; 3].map(|l| unifier.0.var_at_level(l));
+    let plain = unifier.0.var();
+    assert_eq!(0, unifier.0.level(plain));
+    assert_eq!(2, unifier.0.level(inner));
+
+    // Unified variables take the lowest level
+    let Ok(()) = unifier.unify_var_var(escaped, outer) else {
+        panic!("Expected unification to succeed")
+    };
+    assert_eq!(1, unifier.0.level(escaped));
+
+    // Variables inside a value take the level of the variable it is bound to
+    let snapshot = unifier.0.snapshot();
+    let Ok(()) = unifier.unify_var_value(
+        outer,
+        Type::Function {
+            arg: Box::new(nested.into()),
+            ret: Box::new(typ::unit()),
+        },
+    ) else {
+        panic!("Expected unification to succeed")
+    };
+    assert_eq!(1, unifier.0.level(nested));
+    unifier.0.rollback_to(snapshot);
+    assert_eq!(2, unifier.0.level(nested));
+
+    let value = typ::function(inner, escaped);
+    let scheme = unifier.0.generalize_level(value, 1);
+    assert_eq!(vec![inner], scheme.vars);
+
+    let ValueOrVar::Value(Type::Function { arg, .. }) =
+        unifier.0.instantiate_level(&scheme, 3)
+    else {
+        panic!("Expected a function")
+    };
+    let ValueOrVar::Var(fresh) = *arg else {
+        panic!("Expected a variable")
+    };
+    assert_eq!(3, unifier.0.level(fresh));
+}
@@ -0,0 +1,92 @@
+use pretty_assertions::assert_eq;
+use serde::{Serialize, de::DeserializeOwned};
+use value_type::value_type;
+
+use crate::{
+    substitution,
+    unification::{Table, TableState, Unifier, Unify, ValueOrVar, Var},
+};
+
+// Only equal names unify
+#[value_type]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Name(String);
+
+#[value_type]
+struct Mismatch;
+
+impl Unify for Name {
+    type Error = Mismatch;
+
+    fn unify(
+        left: ValueOrVar<Self>,
+        right: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> Result<(), Self::Error> {
+        match (left, right) {
+            (ValueOrVar::Var(left), ValueOrVar::Var(right)) => {
+                unifier.unify_var_var(left, right)
+            }
+            (ValueOrVar::Var(var), ValueOrVar::Value(value))
+            | (ValueOrVar::Value(value), ValueOrVar::Var(var)) => {
+                unifier.unify_var_value(var, value)
+            }
+            (ValueOrVar::Value(left), ValueOrVar::Value(right)) => {
+                Self::merge(&left, &right).map(|_| ())
+            }
+        }
+    }
+
+    fn merge(left: &Self, right: &Self) -> Result<Self, Self::Error> {
+        if left == right {
+            Ok(left.clone())
+        } else {
+            Err(Mismatch)
+        }
+    }
+}
+
+fn name(name: &str) -> ValueOrVar<Name> {
+    ValueOrVar::Value(Name(name.to_owned()))
+}
+
+fn round_trip<V: Serialize + DeserializeOwned>(value: &V) -> V {
+    let json = serde_json::to_string(value).expect("Serializing can't fail");
+    serde_json::from_str(&json).expect("Deserializing what was serialized")
+}
+
+#[test]
+fn vars() {
+    assert_eq!(Var(3), round_trip(&Var(3)));
+    assert_eq!(substitution::Var(3), round_trip(&substitution::Var(3)));
+    assert_eq!(name("x"), round_trip(&name("x")));
+    assert_eq!(
+        ValueOrVar::<Name>::Var(Var(1)),
+        round_trip(&ValueOrVar::Var(Var(1)))
+    );
+}
+
+#[test]
+fn table_state() -> Result<(), Mismatch> {
+    let mut table = Table::<Name>::with_var_offset(10);
+    let a = table.named_var("a");
+    let [b, c] = table.fresh_array();
+    table.constraint_now(ValueOrVar::Var(a), ValueOrVar::Var(b))?;
+    table.constraint_now(ValueOrVar::Var(b), name("x"))?;
+    table.constraint(ValueOrVar::Var(c), name("y"));
+
+    let state = table.export();
+    let restored: TableState<Name> = round_trip(&state);
+    assert_eq!(state, restored);
+
+    // The restored variables mean the same thing and new ones come after them
+    let mut table = Table::import(restored);
+    let d = table.var();
+    assert_eq!(Var(13), d);
+    assert_eq!("a", table.fmt_var(a));
+    table.constraint(ValueOrVar::Var(d), ValueOrVar::Var(c));
+    let types = table.unify()?;
+    assert_eq!(name("x"), types[&a]);
+    assert_eq!(name("y"), types[&d]);
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use pretty_assertions::assert_eq;
+
+use super::lambda::{builders::typ, implementation::Type};
+use crate::unification::{Table, TableState, Unifier};
+
+#[test]
+fn round_trip() {
+    let mut unifier = Unifier(Table::<Type>::new());
+    let a = unifier.0.named_var("a");
+    let [b, c, d] = unifier.0.fresh_array();
+    let Ok(()) = unifier.unify_var_var(a, b) else {
+        panic!("Expected unification to succeed")
+    };
+    let Ok(()) = unifier.unify_var_value(b, Type::Unit) else {
+        panic!("Expected unification to succeed")
+    };
+    let Ok(()) = unifier.unify_var_var(c, d) else {
+        panic!("Expected unification to succeed")
+    };
+    let mut table = unifier.0;
+    table.constraint_weighted(d.into(), typ::function(a, a), 3);
+
+    let state = table.export();
+    assert_eq!(4, state.representatives.len());
+    assert_eq!(vec![(a, "a".to_owned())], state.names);
+    let mut imported = Table::import(state.clone());
+    assert_eq!(state, imported.export());
+    assert_eq!(table.export_partition(), imported.export_partition());
+
+    let (Ok(expected), Ok(actual)) = (table.unify(), imported.unify()) else {
+        panic!("Expected unification to succeed")
+    };
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn empty() {
+    let state = TableState::<Type> {
+        var_offset: 0,
+        representatives: Vec::new(),
+        values: Vec::new(),
+        names: Vec::new(),
+        constraints: Vec::new(),
+    };
+    assert_eq!(state, Table::import(state.clone()).export());
+}
@@ -0,0 +1,86 @@
+use pretty_assertions::assert_eq;
+use value_type::value_type;
+
+use crate::unification::{Table, Unifier, Unify, ValueOrVar, Var};
+
+// Int is a subtype of Float
+#[value_type(Copy)]
+enum Number {
+    Int,
+    Float,
+}
+
+#[value_type(Copy)]
+struct Mismatch;
+
+impl Unify for Number {
+    type Error = Mismatch;
+
+    fn unify(
+        left: ValueOrVar<Self>,
+        right: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> Result<(), Self::Error> {
+        match (left, right) {
+            (ValueOrVar::Var(left), ValueOrVar::Var(right)) => {
+                unifier.unify_var_var(left, right)
+            }
+            (ValueOrVar::Var(var), ValueOrVar::Value(value))
+            | (ValueOrVar::Value(value), ValueOrVar::Var(var)) => {
+                unifier.unify_var_value(var, value)
+            }
+            (ValueOrVar::Value(left), ValueOrVar::Value(right)) => {
+                if unifier.subtyping() {
+                    Self::join(&left, &right).map(|_| ())
+                } else {
+                    Self::merge(&left, &right).map(|_| ())
+                }
+            }
+        }
+    }
+
+    // Leave variables alone so values meet in the table rather than in unify
+    fn normalize(
+        value: ValueOrVar<Self>,
+        _: &mut Unifier<Self>,
+    ) -> ValueOrVar<Self> {
+        value
+    }
+
+    fn merge(left: &Self, right: &Self) -> Result<Self, Self::Error> {
+        if left == right {
+            Ok(*left)
+        } else {
+            Err(Mismatch)
+        }
+    }
+
+    fn join(left: &Self, right: &Self) -> Result<Self, Self::Error> {
+        Ok(if left == right { *left } else { Number::Float })
+    }
+}
+
+fn numbers() -> (Table<Number>, [Var; 3]) {
+    let mut table = Table::new();
+    let [a, b, c] = table.fresh_array();
+    table.constraint(ValueOrVar::Var(a), ValueOrVar::Value(Number::Int));
+    table.constraint(ValueOrVar::Var(b), ValueOrVar::Value(Number::Float));
+    table.constraint(ValueOrVar::Var(c), ValueOrVar::Value(Number::Int));
+    table.constraint(ValueOrVar::Var(a), ValueOrVar::Var(b));
+    table.constraint(ValueOrVar::Var(c), ValueOrVar::Value(Number::Float));
+    (table, [a, b, c])
+}
+
+#[test]
+fn unify_subtyping() {
+    let (table, _) = numbers();
+    assert_eq!(Err(Mismatch), table.unify().map(|_| ()));
+
+    let (table, [a, b, c]) = numbers();
+    let Ok(types) = table.unify_subtyping() else {
+        panic!("Expected unification to succeed")
+    };
+    for var in [a, b, c] {
+        assert_eq!(ValueOrVar::Value(Number::Float), types[&var]);
+    }
+}
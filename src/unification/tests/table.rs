@@ -0,0 +1,841 @@
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+use pretty_assertions::assert_eq;
+use value_type::value_type;
+
+use super::lambda::{
+    builders::{mono_typ, typ},
+    implementation::{Type, TypeError},
+};
+use crate::{
+    substitution,
+    unification::{
+        BudgetError, BudgetExceeded, ConstraintOrder, CowConstraints,
+        CowValueOrVar, SpannedError, Table, TableBuilder, Unifier, Unify,
+        UnifyStats, UnresolvedVariableError, ValueOrVar, Var, resolve_mono_all,
+    },
+};
+
+#[test]
+fn builder() {
+    let mut table = Table::builder().dedup().recovery(Type::Unit).build();
+    let a = table.var();
+    let b = table.var();
+    table.constraint(a.into(), typ::unit());
+    // Conflicts with the first constraint, included twice but deduplicated
+    table.constraint(a.into(), typ::function(typ::unit(), typ::unit()));
+    table.constraint(a.into(), typ::function(typ::unit(), typ::unit()));
+    // Infinite type, b is still unbound after this fails so it recovers to
+    // Unit
+    table.constraint(b.into(), typ::function(b, b));
+
+    let (types, errors) = table.unify_with_recovery();
+
+    assert_eq!(
+        vec![
+            TypeError::IncompatibleTypes(
+                mono_typ::unit(),
+                mono_typ::function(typ::unit(), typ::unit())
+            ),
+            TypeError::InfiniteType(b, mono_typ::function(b, b)),
+        ],
+        errors
+    );
+    assert_eq!(typ::unit(), types[&a]);
+    assert_eq!(ValueOrVar::Value(Type::Unit), types[&b]);
+}
+
+#[test]
+fn trace() {
+    let traced = Arc::new(Mutex::new(Vec::new()));
+    let steps = || traced.lock().expect("The callback doesn't panic");
+    let mut table = Table::builder()
+        .trace({
+            let traced = Arc::clone(&traced);
+            move |left: &ValueOrVar<Type>, right: &ValueOrVar<Type>| {
+                traced
+                    .lock()
+                    .expect("The test doesn't panic while holding the lock")
+                    .push((left.clone(), right.clone()));
+            }
+        })
+        .build();
+    let [a, b] = table.fresh_array();
+    table.constraint(a.into(), typ::function(b, typ::unit()));
+    table.constraint(b.into(), typ::unit());
+    let mut fork = table.fork();
+    let Ok(_) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+
+    // Only the constraints themselves, not the calls made while unifying them
+    assert_eq!(
+        vec![
+            (a.into(), typ::function(b, typ::unit())),
+            (b.into(), typ::unit()),
+        ],
+        *steps()
+    );
+
+    // Forks report to the same callback
+    steps().clear();
+    assert_eq!(Ok(()), fork.constraint_now(a.into(), b.into()));
+    assert_eq!(vec![(a.into(), b.into())], *steps());
+}
+
+#[test]
+fn send() {
+    fn assert_send<T: Send>() {}
+    // Including everything the builder and deferred constraints store
+    assert_send::<Table<Type>>();
+    assert_send::<Table<Type, String>>();
+    assert_send::<TableBuilder<Type>>();
+}
+
+#[test]
+fn var_offset() {
+    let mut table = Table::with_var_offset(100);
+    let [a, b, c] = table.fresh_array();
+    assert_eq!([Var(100), Var(101), Var(102)], [a, b, c]);
+    assert_eq!("?101", table.fmt_var(b));
+    table.constraint(a.into(), typ::function(b, c));
+    table.constraint(b.into(), typ::unit());
+
+    let state = table.export();
+    assert_eq!(100, state.var_offset);
+    let (Ok(expected), Ok(actual)) =
+        (table.unify(), Table::import(state).unify())
+    else {
+        panic!("Expected unification to succeed")
+    };
+    assert_eq!(typ::unit(), expected[&b]);
+    let mut vars = expected.keys().copied().collect::<Vec<_>>();
+    vars.sort_by_key(|&Var(var)| var);
+    assert_eq!(vec![a, b, c], vars);
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn var_limit() {
+    let mut table = Table::<Type>::builder().var_limit(2).build();
+    assert!(table.try_var().is_ok());
+    assert!(table.try_var().is_ok());
+    assert!(table.try_var().is_err());
+}
+
+#[test]
+#[should_panic(expected = "Variable limit of 2 exceeded")]
+fn var_limit_import_partition() {
+    let mut table = Table::<Type>::builder().var_limit(2).build();
+    // Needs Var(0), Var(1) and Var(2)
+    let _ = table.import_partition(&[(Var(0), Var(2))]);
+}
+
+#[test]
+fn vars() {
+    let mut table = Table::<Type>::new();
+    let first = table.var();
+    let vars = table.vars(3);
+    let [a, b] = table.fresh_array();
+    assert_eq!(3, vars.len());
+    let all = [&[first][..], &vars, &[a, b]].concat();
+    assert!(all.windows(2).all(|pair| pair[0].0 + 1 == pair[1].0));
+
+    let mut table = Table::<Type>::builder().var_limit(2).build();
+    assert_eq!(2, table.vars(2).len());
+    assert!(table.try_var().is_err());
+}
+
+#[test]
+fn constraints_from_substitution() -> Result<(), Box<dyn std::error::Error>> {
+    // Work out which nodes have The Property (TM) ...
+    let mut substitution = substitution::Table::new();
+    let leaf_true = substitution.var();
+    let leaf_false = substitution.var();
+    let internal = substitution.var();
+    substitution.fact(leaf_true, true)?;
+    substitution.fact(leaf_false, false)?;
+    substitution.dependency(internal, leaf_true);
+    substitution.dependency(internal, leaf_false);
+    let properties = substitution.resolve()?;
+
+    // ... then require that every node with The Property (TM) has type Unit
+    let mut table = Table::new();
+    let types = properties
+        .keys()
+        .map(|&node| (node, table.var()))
+        .collect::<HashMap<_, _>>();
+    for (left, right) in Table::constraints_from(&properties, |node, &p| {
+        p.then(|| (types[node].into(), typ::unit()))
+    }) {
+        table.constraint(left, right);
+    }
+    let Ok(result) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+
+    assert_eq!(typ::unit(), result[&types[&leaf_true]]);
+    assert_eq!(
+        ValueOrVar::Var(types[&leaf_false]),
+        result[&types[&leaf_false]]
+    );
+    assert_eq!(ValueOrVar::Var(types[&internal]), result[&types[&internal]]);
+    Ok(())
+}
+
+#[test]
+fn partition() {
+    let mut table = Table::<Type>::new();
+    let [a, b, c, d] = [table.var(), table.var(), table.var(), table.var()];
+    let Ok(()) = table.import_partition(&[(b, a), (c, b)]) else {
+        panic!("Expected import to succeed")
+    };
+    let partition = table.export_partition();
+    assert_eq!(4, partition.len());
+
+    let mut table = Table::new();
+    let Ok(()) = table.import_partition(&partition) else {
+        panic!("Expected import to succeed")
+    };
+    table.constraint(a.into(), typ::unit());
+    let Ok(result) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+
+    assert_eq!(typ::unit(), result[&b]);
+    assert_eq!(typ::unit(), result[&c]);
+    assert_eq!(ValueOrVar::Var(d), result[&d]);
+}
+
+#[test]
+fn constraints() {
+    let mut table = Table::new();
+    let [a, b] = table.fresh_array();
+    table.constraint(a.into(), typ::unit());
+    table.constraint_weighted(b.into(), a.into(), 1);
+
+    assert_eq!(2, table.constraint_count());
+    assert_eq!(
+        vec![(&a.into(), &typ::unit()), (&b.into(), &a.into())],
+        table.constraints().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn dedup_constraints() {
+    let mut table = Table::<Type, &str>::new_spanned();
+    let [a, b] = table.fresh_array();
+    table.constraint_spanned(a.into(), typ::unit(), "line 1");
+    table.constraint_spanned(b.into(), a.into(), "line 2");
+    table.constraint_spanned(a.into(), typ::unit(), "line 3");
+    table.constraint_spanned(a.into(), typ::function(b, b), "line 4");
+    table.dedup_constraints();
+
+    assert_eq!(
+        vec![
+            (&a.into(), &typ::unit()),
+            (&b.into(), &a.into()),
+            (&a.into(), &typ::function(b, b))
+        ],
+        table.constraints().collect::<Vec<_>>()
+    );
+    // The conflict is reported with the span of the constraint which caused it
+    let Err(SpannedError { span, .. }) = table.unify_spanned() else {
+        panic!("Expected unification to fail")
+    };
+    assert_eq!("line 4", span);
+}
+
+#[test]
+fn constraint_now() {
+    let mut table = Table::new();
+    let [a, b] = table.fresh_array();
+    assert_eq!(Ok(()), table.constraint_now(a.into(), b.into()));
+    assert_eq!(Ok(()), table.constraint_now(b.into(), typ::unit()));
+    assert!(matches!(
+        table.constraint_now(a.into(), typ::function(typ::unit(), typ::unit())),
+        Err(TypeError::IncompatibleTypes(..))
+    ));
+
+    let Ok(types) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+    assert_eq!(typ::unit(), types[&a]);
+}
+
+#[test]
+fn stats() {
+    let mut unifier = Unifier(Table::new());
+    let [a, b, c, d, _] = unifier.0.fresh_array();
+    // Joining two classes of two puts one of them two links from the root
+    for (left, right) in [(a, b), (c, d), (b, d)] {
+        let Ok(()) = unifier.unify_var_var(left, right) else {
+            panic!("Expected unification to succeed")
+        };
+    }
+    let Ok(()) = unifier.unify_var_value(c, Type::Unit) else {
+        panic!("Expected unification to succeed")
+    };
+
+    assert_eq!(
+        UnifyStats {
+            vars: 5,
+            classes: 2,
+            bound: 4,
+            max_chain: 2,
+        },
+        unifier.0.stats()
+    );
+}
+
+#[test]
+fn clear_constraints() {
+    let mut table = Table::new();
+    let [a, b, c] = [table.var(), table.var(), table.var()];
+    let Ok(()) = table.import_partition(&[(b, c)]) else {
+        panic!("Expected import to succeed")
+    };
+    // Would conflict with the constraints added after clearing
+    table.constraint(a.into(), typ::function(typ::unit(), typ::unit()));
+    table.constraint(b.into(), typ::function(typ::unit(), typ::unit()));
+    table.clear_constraints();
+
+    let d = table.var();
+    table.constraint(a.into(), typ::unit());
+    table.constraint(b.into(), d.into());
+    table.constraint(c.into(), typ::unit());
+    let Ok(result) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+
+    assert_eq!(typ::unit(), result[&a]);
+    // Still unified with c
+    assert_eq!(typ::unit(), result[&b]);
+    assert_eq!(typ::unit(), result[&d]);
+}
+
+#[test]
+fn snapshot() {
+    let mut table = Table::<Type>::new();
+    let [a, b] = table.fresh_array();
+    table.constraint(a.into(), typ::unit());
+
+    let snapshot = table.snapshot();
+    let c = table.named_var("c");
+    let Ok(()) = table.import_partition(&[(a, b)]) else {
+        panic!("Expected import to succeed")
+    };
+    table.constraint(b.into(), typ::function(typ::unit(), typ::unit()));
+    table.rollback_to(snapshot);
+
+    // c is gone and a and b are no longer unified
+    assert_eq!(2, table.export_partition().len());
+    assert_eq!(c, table.var());
+    assert_eq!(format!("?{}", c.0), table.fmt_var(c));
+    let snapshot = table.snapshot();
+    table.constraint(b.into(), typ::unit());
+    table.commit(snapshot);
+    let Ok(result) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+    assert_eq!(typ::unit(), result[&a]);
+    assert_eq!(typ::unit(), result[&b]);
+}
+
+#[test]
+fn equivalence_classes() {
+    let mut unifier = Unifier(Table::<Type>::new());
+    let [a, b, c, d] = unifier.0.fresh_array();
+    let Ok(()) = unifier.unify_var_var(a, c) else {
+        panic!("Expected unification to succeed")
+    };
+    let Ok(()) = unifier.unify_var_value(c, Type::Unit) else {
+        panic!("Expected unification to succeed")
+    };
+    let Ok(()) = unifier.unify_var_var(b, d) else {
+        panic!("Expected unification to succeed")
+    };
+
+    let first = unifier.representative(a);
+    let second = unifier.representative(d);
+    assert_eq!(first, unifier.representative(c));
+    assert_eq!(second, unifier.representative(b));
+    assert_ne!(first, second);
+    assert_eq!(
+        HashMap::from([(first, vec![a, c]), (second, vec![b, d])]),
+        unifier.0.equivalence_classes()
+    );
+}
+
+#[test]
+fn fork() {
+    let mut unifier = Unifier(Table::new());
+    let [a, b, c] = unifier.0.fresh_array();
+    let Ok(()) = unifier.unify_var_var(a, b) else {
+        panic!("Expected unification to succeed")
+    };
+    let Ok(()) = unifier.unify_var_value(b, Type::Unit) else {
+        panic!("Expected unification to succeed")
+    };
+    let mut table = unifier.0;
+    table.constraint(c.into(), a.into());
+
+    let mut branch = table.fork();
+    assert_eq!(table.export_partition(), branch.export_partition());
+    branch.constraint(c.into(), typ::function(typ::unit(), typ::unit()));
+    assert!(branch.unify().is_err());
+
+    // The original is untouched by the branch
+    assert_eq!(1, table.constraint_count());
+    let Ok(types) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+    assert_eq!(typ::unit(), types[&c]);
+}
+
+#[test]
+fn fork_keeps_links() {
+    let mut table = Table::<Type>::new();
+    let [a, b, c, d] = table.fresh_array();
+    let Ok(()) = table.import_partition(&[(a, b), (c, d), (b, d)]) else {
+        panic!("Expected import to succeed")
+    };
+
+    let mut branch = table.fork();
+    assert_eq!(2, table.stats().max_chain);
+    assert_eq!(table.stats(), branch.stats());
+    assert_eq!(table.dump_forest(), branch.dump_forest());
+}
+
+#[test]
+fn dump_forest() {
+    let mut table = Table::<Type>::new();
+    let [a, b, c, d, lone] = [(); 5].map(|()| table.var());
+    // Two trees of rank 1, the second is linked under the first so a is two
+    // links away from its root
+    let Ok(()) = table.import_partition(&[(a, b), (c, d), (b, d)]) else {
+        panic!("Expected import to succeed")
+    };
+    let forest = table.dump_forest();
+    let parent = |var| forest.iter().find(|&&(v, _)| v == var).unwrap().1;
+    let root = parent(parent(a).unwrap()).unwrap();
+    assert_eq!(None, parent(root));
+    assert_ne!(Some(root), parent(a));
+    assert_eq!(None, parent(lone));
+    assert_eq!(2, forest.iter().filter(|(_, p)| p.is_none()).count());
+
+    // Finding the roots compresses the underlying paths but not the dump
+    let _ = table.export_partition();
+    assert_eq!(forest, table.dump_forest());
+
+    // Links made since a snapshot go when it is rolled back
+    let snapshot = table.snapshot();
+    let Ok(()) = table.import_partition(&[(a, lone)]) else {
+        panic!("Expected import to succeed")
+    };
+    assert_ne!(forest, table.dump_forest());
+    table.rollback_to(snapshot);
+    assert_eq!(forest, table.dump_forest());
+}
+
+#[test]
+fn unify_min_conflict() {
+    let mut table = Table::new();
+    let a = table.var();
+    let b = table.var();
+    // Any two of these are fine but all three conflict
+    table.constraint_weighted(a.into(), typ::unit(), 3);
+    table.constraint_weighted(a.into(), b.into(), 1);
+    table.constraint_weighted(
+        b.into(),
+        typ::function(typ::unit(), typ::unit()),
+        2,
+    );
+
+    let (types, dropped) = table.unify_min_conflict();
+
+    assert_eq!(vec![(a.into(), b.into())], dropped);
+    assert_eq!(typ::unit(), types[&a]);
+    assert_eq!(typ::function(typ::unit(), typ::unit()), types[&b]);
+}
+
+#[test]
+fn unify_ordered() {
+    fn conflict(order: ConstraintOrder<Type>) -> TypeError {
+        let mut table = Table::new();
+        let a = table.var();
+        table.constraint(a.into(), typ::unit());
+        table.constraint(a.into(), typ::function(typ::unit(), typ::unit()));
+        let Err(error) = table.unify_ordered(order) else {
+            panic!("Expected unification to fail")
+        };
+        error
+    }
+
+    let function = Type::Function {
+        arg: Box::new(typ::unit()),
+        ret: Box::new(typ::unit()),
+    };
+    // The conflict is reported against whichever value a was bound to first
+    assert_eq!(
+        TypeError::IncompatibleTypes(Type::Unit, function.clone()),
+        conflict(ConstraintOrder::Fifo)
+    );
+    assert_eq!(
+        TypeError::IncompatibleTypes(function.clone(), Type::Unit),
+        conflict(ConstraintOrder::Lifo)
+    );
+    // Functions first
+    assert_eq!(
+        TypeError::IncompatibleTypes(function, Type::Unit),
+        conflict(ConstraintOrder::SortedBy(|(_, left), (_, right)| {
+            let unit = ValueOrVar::Value(Type::Unit);
+            (*left == unit).cmp(&(*right == unit))
+        }))
+    );
+}
+
+#[test]
+fn unify_spanned() {
+    let mut table = Table::<Type, &str>::new_spanned();
+    let a = table.var();
+    table.constraint_spanned(a.into(), typ::unit(), "line 1");
+    table.constraint_spanned(
+        a.into(),
+        typ::function(typ::unit(), typ::unit()),
+        "line 2",
+    );
+
+    let Err(SpannedError { error, span }) = table.unify_spanned() else {
+        panic!("Expected unification to fail")
+    };
+    assert!(matches!(error, TypeError::IncompatibleTypes(..)));
+    assert_eq!("line 2", span);
+}
+
+#[test]
+fn from_substitution() {
+    let mut table = Table::new();
+    let [a, b, c, d] = table.fresh_array();
+    table.constraint(a.into(), typ::function(c, typ::unit()));
+    table.constraint(b.into(), d.into());
+    let Ok(first) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+
+    // The second phase picks up where the first left off
+    let mut table = Table::from_substitution(first);
+    assert_eq!(Var(4), table.var());
+    table.constraint(c.into(), b.into());
+    table.constraint(d.into(), typ::unit());
+    let Ok(second) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+    assert_eq!(typ::unit(), second[&b]);
+    assert_eq!(typ::unit(), second[&c]);
+    assert_eq!(typ::function(c, typ::unit()), second[&a]);
+
+    let mut table = Table::from_substitution(HashMap::from([(
+        Var(0),
+        typ::function(Var(2), Var(2)),
+    )]));
+    // Variables inside values are recreated too
+    assert_eq!(Var(3), table.var());
+    table.constraint(Var(0).into(), typ::unit());
+    assert!(table.unify().is_err());
+}
+
+#[test]
+fn unify_with_unbound() {
+    let mut table = Table::new();
+    let [a, b, c, d] = table.fresh_array();
+    table.constraint(a.into(), typ::function(c, typ::unit()));
+    table.constraint(b.into(), d.into());
+    let Ok((types, unbound)) = table.unify_with_unbound() else {
+        panic!("Expected unification to succeed")
+    };
+    // c is only unbound inside a's value, b and d share a representative
+    let ValueOrVar::Var(root) = types[&b] else {
+        panic!("Expected b to be unbound")
+    };
+    assert_eq!(HashSet::from([root, c]), unbound);
+    assert_eq!(typ::function(c, typ::unit()), types[&a]);
+}
+
+#[test]
+fn unify_with_budget() {
+    fn constrained() -> (Table<Type>, [Var; 2]) {
+        let mut table = Table::new();
+        let [a, b] = table.fresh_array();
+        table.constraint(a.into(), typ::unit());
+        table.constraint(b.into(), typ::function(a, a));
+        (table, [a, b])
+    }
+
+    // Only the first constraint fits in the budget
+    let (table, [a, b]) = constrained();
+    let Err(BudgetError::Exceeded(BudgetExceeded { max_steps, partial })) =
+        table.unify_with_budget(1)
+    else {
+        panic!("Expected the budget to run out")
+    };
+    assert_eq!(1, max_steps);
+    assert_eq!(typ::unit(), partial[&a]);
+    assert_eq!(ValueOrVar::Var(b), partial[&b]);
+
+    let (table, [_, b]) = constrained();
+    let Ok(types) = table.unify_with_budget(2) else {
+        panic!("Expected unification to succeed")
+    };
+    assert_eq!(typ::function(typ::unit(), typ::unit()), types[&b]);
+
+    let mut table = Table::new();
+    let a = table.var();
+    table.constraint(a.into(), typ::unit());
+    table.constraint(a.into(), typ::function(typ::unit(), typ::unit()));
+    assert!(matches!(
+        table.unify_with_budget(usize::MAX),
+        Err(BudgetError::Unify(TypeError::IncompatibleTypes(..)))
+    ));
+}
+
+// Natural numbers, unifying two successors unifies their predecessors through
+// the unifier
+#[value_type]
+enum Nat {
+    Zero,
+    Succ(Box<ValueOrVar<Nat>>),
+}
+
+impl Unify for Nat {
+    type Error = Infallible;
+
+    fn unify(
+        left: ValueOrVar<Self>,
+        right: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> Result<(), Self::Error> {
+        match (left, right) {
+            (ValueOrVar::Var(left), ValueOrVar::Var(right)) => {
+                unifier.unify_var_var(left, right)
+            }
+            (ValueOrVar::Var(var), ValueOrVar::Value(value))
+            | (ValueOrVar::Value(value), ValueOrVar::Var(var)) => {
+                unifier.unify_var_value(var, value)
+            }
+            (
+                ValueOrVar::Value(Nat::Succ(left)),
+                ValueOrVar::Value(Nat::Succ(right)),
+            ) => unifier.unify(*left, *right),
+            (ValueOrVar::Value(_), ValueOrVar::Value(_)) => Ok(()),
+        }
+    }
+
+    fn merge(left: &Self, _: &Self) -> Result<Self, Self::Error> {
+        Ok(left.clone())
+    }
+}
+
+#[test]
+fn unify_with_budget_counts_nested_calls() {
+    // One call for each Succ and one for the var at the bottom
+    fn nested() -> (Table<Nat>, Var) {
+        let mut table = Table::new();
+        let a = table.var();
+        let succ = |nat| ValueOrVar::Value(Nat::Succ(Box::new(nat)));
+        let three =
+            (0..3).fold(ValueOrVar::Value(Nat::Zero), |nat, _| succ(nat));
+        let a_plus_three = (0..3).fold(ValueOrVar::Var(a), |nat, _| succ(nat));
+        table.constraint(three, a_plus_three);
+        (table, a)
+    }
+
+    let (table, a) = nested();
+    let Err(BudgetError::Exceeded(BudgetExceeded { partial, .. })) =
+        table.unify_with_budget(3)
+    else {
+        panic!("Expected the budget to run out")
+    };
+    assert_eq!(ValueOrVar::Var(a), partial[&a]);
+
+    let (table, a) = nested();
+    let Ok(types) = table.unify_with_budget(4) else {
+        panic!("Expected unification to succeed")
+    };
+    assert_eq!(ValueOrVar::Value(Nat::Zero), types[&a]);
+}
+
+#[test]
+fn resolve_mono_all_stops_at_unresolved() {
+    let mut table = Table::new();
+    let a = table.var();
+    let b = table.var();
+    table.constraint(a.into(), typ::unit());
+    let Ok(types) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+
+    let walked = Cell::new(0);
+    let result = resolve_mono_all(
+        [typ::unit(), a.into(), b.into(), typ::unit()],
+        &types,
+        |typ, _| {
+            walked.set(walked.get() + 1);
+            Ok(typ)
+        },
+    );
+
+    assert_eq!(Err(UnresolvedVariableError(b)), result);
+    // Only the values before b were resolved
+    assert_eq!(2, walked.get());
+}
+
+#[test]
+fn named_vars() {
+    let mut table = Table::<Type>::new();
+    let param = table.named_var("T_param0");
+    let ret = table.named_var("T_ret");
+    let anonymous = table.var();
+    let Ok(()) = table.import_partition(&[(param, ret)]) else {
+        panic!("Expected import to succeed")
+    };
+
+    // Whichever of the two ended up as the representative
+    let root = table
+        .dump_forest()
+        .into_iter()
+        .find_map(|(var, parent)| (var == param).then_some(parent))
+        .flatten()
+        .unwrap_or(param);
+    let expected = if root == param { "T_param0" } else { "T_ret" };
+    assert_eq!(expected, table.fmt_var(param));
+    assert_eq!(expected, table.fmt_var(ret));
+    assert_eq!(format!("?{}", anonymous.0), table.fmt_var(anonymous));
+}
+
+#[test]
+fn display_as() {
+    assert_eq!("?t3", Var(3).display_as("?t").to_string());
+    assert_eq!("'3", Var(3).display_as("'").to_string());
+    assert_eq!("Var(3)", Var(3).to_string());
+}
+
+#[test]
+fn probe_shallow() {
+    let mut unifier = Unifier(Table::new());
+    let [a, b, c] = unifier.0.fresh_array();
+    let Ok(()) = unifier.unify_var_var(a, b) else {
+        panic!("Expected unification to succeed")
+    };
+    let Ok(()) = unifier.unify_var_value(c, Type::Unit) else {
+        panic!("Expected unification to succeed")
+    };
+
+    let shared = &unifier;
+    let representative = shared.probe_shallow(a);
+    assert_eq!(representative, shared.probe_shallow(b));
+    assert_eq!(ValueOrVar::Value(Type::Unit), shared.probe_shallow(c));
+    assert_eq!(representative, unifier.probe(a));
+}
+
+#[test]
+fn is_bound() {
+    let mut unifier = Unifier(Table::new());
+    let [a, b] = unifier.0.fresh_array();
+    let Ok(()) = unifier.unify_var_var(a, b) else {
+        panic!("Expected unification to succeed")
+    };
+    assert!(!unifier.is_bound(a));
+    let Ok(()) = unifier.unify_var_value(b, Type::Unit) else {
+        panic!("Expected unification to succeed")
+    };
+    assert!(unifier.is_bound(a));
+}
+
+#[test]
+fn unify_var_value_ref() {
+    let mut unifier = Unifier(Table::new());
+    let [a, b] = unifier.0.fresh_array();
+    let unit = Type::Unit;
+    let Ok(()) = unifier.unify_var_value_ref(a, &unit) else {
+        panic!("Expected unification to succeed")
+    };
+    let Ok(()) = unifier.unify_var_var(a, b) else {
+        panic!("Expected unification to succeed")
+    };
+
+    let function = Type::Function {
+        arg: Box::new(typ::unit()),
+        ret: Box::new(typ::unit()),
+    };
+    assert!(matches!(
+        unifier.unify_var_value_ref(b, &function),
+        Err(TypeError::IncompatibleTypes(..))
+    ));
+    assert_eq!(ValueOrVar::Value(unit), unifier.probe(b));
+}
+
+// Counts the number of times it has been cloned
+#[derive(Debug)]
+struct Counted(Rc<Cell<usize>>);
+
+impl Clone for Counted {
+    fn clone(&self) -> Self {
+        self.0.set(self.0.get() + 1);
+        Counted(Rc::clone(&self.0))
+    }
+}
+
+impl Unify for Counted {
+    type Error = Infallible;
+
+    fn unify(
+        left: ValueOrVar<Self>,
+        right: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> Result<(), Self::Error> {
+        match (left, right) {
+            (ValueOrVar::Var(left), ValueOrVar::Var(right)) => {
+                unifier.unify_var_var(left, right)
+            }
+            (ValueOrVar::Var(var), ValueOrVar::Value(value))
+            | (ValueOrVar::Value(value), ValueOrVar::Var(var)) => {
+                unifier.unify_var_value(var, value)
+            }
+            (ValueOrVar::Value(_), ValueOrVar::Value(_)) => Ok(()),
+        }
+    }
+
+    fn merge(left: &Self, _: &Self) -> Result<Self, Self::Error> {
+        Ok(left.clone())
+    }
+}
+
+#[test]
+fn unify_cow() {
+    let clones = Rc::new(Cell::new(0));
+    let borrowed = Counted(Rc::clone(&clones));
+    let mut table = Table::new();
+    let a = table.var();
+    let b = table.var();
+    let mut constraints = CowConstraints::new();
+
+    // Nothing is cloned until the constraints are unified
+    constraints.constraint(a.into(), (&borrowed).into());
+    constraints.constraint(
+        b.into(),
+        CowValueOrVar::from(ValueOrVar::Value(Counted(Rc::clone(&clones)))),
+    );
+    assert_eq!(0, clones.get());
+
+    let Ok(result) = table.unify_cow(constraints);
+    assert!(clones.get() > 0);
+    assert!(matches!(result[&a], ValueOrVar::Value(_)));
+    assert!(matches!(result[&b], ValueOrVar::Value(_)));
+}
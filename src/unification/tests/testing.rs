@@ -0,0 +1,30 @@
+use pretty_assertions::assert_eq;
+
+use super::lambda::{
+    builders::typ,
+    implementation::{Type, TypeError},
+};
+use crate::unification::testing::Harness;
+
+#[test]
+fn harness() {
+    let mut harness = Harness::<Type>::new();
+    let [a, b] = [harness.var(), harness.var()];
+    harness.assert_unifies(a.into(), typ::function(b, typ::unit()));
+    harness.assert_unifies(b.into(), typ::unit());
+    assert_eq!(typ::function(typ::unit(), typ::unit()), harness.resolved(a));
+
+    let error = harness.assert_conflict(b.into(), typ::function(a, a));
+    assert!(matches!(error, TypeError::IncompatibleTypes(..)));
+    // Nothing changed
+    assert_eq!(typ::unit(), harness.resolved(b));
+}
+
+#[test]
+#[should_panic(expected = "to unify")]
+fn unexpected_conflict() {
+    let mut harness = Harness::<Type>::new();
+    let a = harness.var();
+    harness.assert_unifies(a.into(), typ::unit());
+    harness.assert_unifies(a.into(), typ::function(typ::unit(), typ::unit()));
+}
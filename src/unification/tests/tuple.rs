@@ -0,0 +1,42 @@
+use pretty_assertions::assert_eq;
+
+use super::lambda::{
+    builders::{mono_typ, typ},
+    implementation::{Type, TypeError},
+};
+use crate::unification::{Table, TupleError, ValueOrVar};
+
+fn pair(first: Type, second: Type) -> ValueOrVar<(Type, Type)> {
+    ValueOrVar::Value((first, second))
+}
+
+#[test]
+fn pairs() {
+    let unit_to_unit = mono_typ::function(typ::unit(), typ::unit());
+    let mut table = Table::new();
+    let a = table.var();
+    let b = table.var();
+    table
+        .constraint(ValueOrVar::Var(a), pair(Type::Unit, unit_to_unit.clone()));
+    table.constraint(ValueOrVar::Var(a), ValueOrVar::Var(b));
+    table
+        .constraint(ValueOrVar::Var(b), pair(Type::Unit, unit_to_unit.clone()));
+    let Ok(result) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+    assert_eq!(pair(Type::Unit, unit_to_unit.clone()), result[&b]);
+
+    // The first components agree, the second don't
+    let mut table = Table::new();
+    let a = table.var();
+    table.constraint(ValueOrVar::Var(a), pair(Type::Unit, Type::Unit));
+    table
+        .constraint(ValueOrVar::Var(a), pair(Type::Unit, unit_to_unit.clone()));
+    assert_eq!(
+        Err(TupleError::Second(TypeError::IncompatibleTypes(
+            Type::Unit,
+            unit_to_unit
+        ))),
+        table.unify().map(|_| ())
+    );
+}
@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+
+use pretty_assertions::assert_eq;
+
+use super::lambda::{
+    builders::{mono_typ, typ},
+    implementation::Type,
+};
+use crate::unification::{Table, Unify, ValueOrVar};
+
+#[test]
+fn accessors() {
+    let var = Table::<Type>::new().var();
+    let value = ValueOrVar::Value(Type::Unit);
+    let variable = ValueOrVar::<Type>::Var(var);
+
+    assert_eq!(None, value.as_var());
+    assert_eq!(Some(&Type::Unit), value.as_value());
+    assert!(value.is_value());
+    assert!(!value.is_var());
+    assert_eq!(Some(Type::Unit), value.into_value());
+
+    assert_eq!(Some(var), variable.as_var());
+    assert_eq!(None, variable.as_value());
+    assert!(variable.is_var());
+    assert!(!variable.is_value());
+    assert_eq!(None, variable.into_value());
+}
+
+#[test]
+fn map() {
+    let var = Table::<Type>::new().var();
+    let value = ValueOrVar::Value(Type::Unit);
+    let variable = ValueOrVar::<Type>::Var(var);
+
+    let is_unit = |typ: &Type| matches!(typ, Type::Unit);
+    assert_eq!(ValueOrVar::Value(true), value.map_ref(is_unit));
+    assert_eq!(ValueOrVar::Var(var), variable.map_ref(is_unit));
+    assert_eq!(ValueOrVar::Value(Some(Type::Unit)), value.map(Some));
+    assert_eq!(ValueOrVar::Var(var), variable.map(Some));
+}
+
+#[test]
+fn resolve_auto() {
+    let mut table = Table::new();
+    let [a, b, unbound] = table.fresh_array();
+    table.constraint(a.into(), typ::unit());
+    table.constraint(b.into(), typ::function(a, unbound));
+    let Ok(types) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+
+    assert_eq!(
+        typ::function(typ::function(typ::unit(), unbound), typ::unit()),
+        typ::function(b, a).resolve_auto(&types)
+    );
+}
+
+#[test]
+fn substitute() {
+    let mut table = Table::new();
+    let [a, b, unbound] = table.fresh_array();
+    table.constraint(a.into(), typ::unit());
+    table.constraint(b.into(), typ::function(unbound, a));
+    let Ok(types) = table.unify() else {
+        panic!("Expected unification to succeed")
+    };
+
+    // Only the nested variables are replaced, the value itself is kept
+    assert_eq!(
+        mono_typ::function(typ::unit(), typ::function(unbound, typ::unit())),
+        mono_typ::function(a, b).substitute(&types)
+    );
+    assert_eq!(mono_typ::unit(), mono_typ::unit().substitute(&types));
+}
+
+#[test]
+fn hash() {
+    let var = Table::<Type>::new().var();
+    let set = HashSet::from([
+        ValueOrVar::Value(Type::Unit),
+        ValueOrVar::Var(var),
+        ValueOrVar::Value(Type::Unit),
+        typ::function(typ::unit(), var),
+    ]);
+    assert_eq!(3, set.len());
+    assert!(set.contains(&ValueOrVar::Var(var)));
+}
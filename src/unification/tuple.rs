@@ -0,0 +1,81 @@
+//! [`Unify`] implementations for tuples
+
+use std::convert::Infallible;
+
+use super::{Unifier, Unify, ValueOrVar};
+
+/// Error produced when unifying tuples, identifies the component which failed
+///
+/// Pairs leave `C` as [`Infallible`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum TupleError<A, B, C = Infallible> {
+    /// The first components failed to unify
+    #[error("First component: {0}")]
+    First(A),
+    /// The second components failed to unify
+    #[error("Second component: {0}")]
+    Second(B),
+    /// The third components failed to unify
+    #[error("Third component: {0}")]
+    Third(C),
+}
+
+// Tuples don't contain variables of their own so the only structure to
+// decompose is the values themselves, which is exactly what merge does
+fn unify_components<T: Unify>(
+    left: ValueOrVar<T>,
+    right: ValueOrVar<T>,
+    unifier: &mut Unifier<T>,
+) -> Result<(), T::Error> {
+    match (left, right) {
+        (ValueOrVar::Var(left), ValueOrVar::Var(right)) => {
+            unifier.unify_var_var(left, right)
+        }
+        (ValueOrVar::Var(var), ValueOrVar::Value(value))
+        | (ValueOrVar::Value(value), ValueOrVar::Var(var)) => {
+            unifier.unify_var_value(var, value)
+        }
+        (ValueOrVar::Value(left), ValueOrVar::Value(right)) => {
+            T::merge(&left, &right).map(|_| ())
+        }
+    }
+}
+
+impl<A: Unify, B: Unify> Unify for (A, B) {
+    type Error = TupleError<A::Error, B::Error>;
+
+    fn unify(
+        left: ValueOrVar<Self>,
+        right: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> Result<(), Self::Error> {
+        unify_components(left, right, unifier)
+    }
+
+    fn merge(left: &Self, right: &Self) -> Result<Self, Self::Error> {
+        Ok((
+            A::merge(&left.0, &right.0).map_err(TupleError::First)?,
+            B::merge(&left.1, &right.1).map_err(TupleError::Second)?,
+        ))
+    }
+}
+
+impl<A: Unify, B: Unify, C: Unify> Unify for (A, B, C) {
+    type Error = TupleError<A::Error, B::Error, C::Error>;
+
+    fn unify(
+        left: ValueOrVar<Self>,
+        right: ValueOrVar<Self>,
+        unifier: &mut Unifier<Self>,
+    ) -> Result<(), Self::Error> {
+        unify_components(left, right, unifier)
+    }
+
+    fn merge(left: &Self, right: &Self) -> Result<Self, Self::Error> {
+        Ok((
+            A::merge(&left.0, &right.0).map_err(TupleError::First)?,
+            B::merge(&left.1, &right.1).map_err(TupleError::Second)?,
+            C::merge(&left.2, &right.2).map_err(TupleError::Third)?,
+        ))
+    }
+}
@@ -3,13 +3,67 @@ use value_type::value_type;
 
 use super::Unify;
 
+// What the underlying table stores for each set of unified variables: its
+// value if it has one, its level (see Table::var_at_level) and whether values
+// are combined with Unify::join (see Table::unify_subtyping)
 #[value_type]
-pub(crate) struct Value<T>(pub(crate) T);
+pub(crate) struct Value<T> {
+    pub(crate) value: Option<T>,
+    pub(crate) level: u32,
+    pub(crate) subtyping: bool,
+}
+
+impl<T> Value<T> {
+    pub(crate) fn unbound(level: u32) -> Self {
+        Value {
+            value: None,
+            level,
+            subtyping: false,
+        }
+    }
+
+    // The maximum level leaves the level of the variable it is unified with
+    // alone
+    pub(crate) fn bound(value: T) -> Self {
+        Value {
+            value: Some(value),
+            level: u32::MAX,
+            subtyping: false,
+        }
+    }
+
+    // Switches the set it is unified with over to Unify::join without changing
+    // anything else
+    pub(crate) fn subtyping() -> Self {
+        Value {
+            value: None,
+            level: u32::MAX,
+            subtyping: true,
+        }
+    }
+}
 
 impl<T: Unify> UnifyValue for Value<T> {
     type Error = <T as Unify>::Error;
 
     fn unify_values(left: &Self, right: &Self) -> Result<Self, Self::Error> {
-        Ok(Value(Unify::merge(&left.0, &right.0)?))
+        let subtyping = left.subtyping || right.subtyping;
+        let value = match (&left.value, &right.value) {
+            (Some(left), Some(right)) => {
+                let (left, right) = (left.canonicalize(), right.canonicalize());
+                Some(if subtyping {
+                    T::join(&left, &right)?
+                } else {
+                    T::merge(&left, &right)?
+                })
+            }
+            (Some(value), None) | (None, Some(value)) => Some(value.clone()),
+            (None, None) => None,
+        };
+        Ok(Value {
+            value,
+            level: left.level.min(right.level),
+            subtyping,
+        })
     }
 }
@@ -11,7 +11,13 @@ use value_type::value_type;
 use super::{Unify, value::Value};
 
 /// Unification variable
+///
+/// With the `serde` feature variables can be serialized, e.g. to cache the
+/// result of [`Table::unify`](super::Table::unify). A deserialized variable
+/// only refers to the same variable in a table which has created the same
+/// variables, in the same order
 #[value_type(Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Var(pub(crate) u32);
 
 impl fmt::Display for Var {
@@ -21,8 +27,32 @@ impl fmt::Display for Var {
 }
 
 impl Var {
-    pub(crate) fn annotate<T: Unify>(self) -> TypedVar<T> {
-        TypedVar(self.0, PhantomData)
+    /// Display the variable as its number after `prefix`, e.g. `?t3` or `'3`
+    /// to match the syntax of the language being checked
+    ///
+    /// The [`Display`](fmt::Display) impl of `Var` itself is the same as
+    /// [`Debug`](fmt::Debug)
+    #[must_use]
+    pub fn display_as(self, prefix: &str) -> impl fmt::Display {
+        Prefixed(prefix, self.0)
+    }
+
+    // offset is the table's variable offset, see TableBuilder::var_offset
+    pub(crate) fn annotate<T: Unify>(self, offset: u32) -> TypedVar<T> {
+        let index = self
+            .0
+            .checked_sub(offset)
+            .unwrap_or_else(|| panic!("{self:?} is below the variable offset"));
+        TypedVar(index, PhantomData)
+    }
+}
+
+// See Var::display_as
+struct Prefixed<'a>(&'a str, u32);
+
+impl fmt::Display for Prefixed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.0, self.1)
     }
 }
 
@@ -30,14 +60,15 @@ impl Var {
 pub(crate) struct TypedVar<T: Unify>(u32, PhantomData<T>);
 
 impl<T: Unify> TypedVar<T> {
-    pub(crate) fn erase(self) -> Var {
-        Var(self.0)
+    pub(crate) fn erase(self, offset: u32) -> Var {
+        Var(self.0 + offset)
     }
 }
 
 impl<T: Unify> fmt::Debug for TypedVar<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Debug::fmt(&self.erase(), f)
+        // Without the offset this is the index in the underlying table
+        fmt::Debug::fmt(&self.erase(0), f)
     }
 }
 
@@ -68,7 +99,7 @@ impl<T: Unify> Hash for TypedVar<T> {
 }
 
 impl<T: Unify> UnifyKey for TypedVar<T> {
-    type Value = Option<Value<T>>;
+    type Value = Value<T>;
 
     fn index(&self) -> u32 {
         self.0